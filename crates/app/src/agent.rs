@@ -0,0 +1,287 @@
+mod gtp;
+mod heuristic;
+mod mcts;
+mod random;
+
+pub use gtp::GtpAgent;
+pub use heuristic::HeuristicAgent;
+pub use mcts::{Analysis, Candidate, MctsAgent, SearchBudget};
+pub use random::RandomAgent;
+
+use crate::{Controller, GameSeed, GameSettings, PlayerControllers};
+use bevy::{
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, block_on, poll_once},
+};
+use configure::Difficulty;
+use igo_core::{Game, GameHand, Stone};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::{
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+/// What an agent does on its turn.
+#[derive(Debug, Clone, Copy)]
+pub enum Decision {
+    Play(GameHand),
+    Resign,
+}
+
+/// Decides the next hand for the side to move.
+pub trait GoAgent: Send + Sync {
+    /// Long searches should return early once `cancel` is set; the decision
+    /// is discarded then.
+    fn select_hand(&mut self, game: &Game, turn: Stone, cancel: &AtomicBool) -> Decision;
+
+    /// Called before the first hand with the komi White receives.
+    fn set_komi(&mut self, _komi: f32) {}
+
+    /// Whether the same seed always yields the same hands; `false` when the
+    /// choice depends on wall-clock time or on another program.
+    fn replayable(&self) -> bool {
+        true
+    }
+}
+
+// Registry names of the MCTS agents picked by `SelectedAgents::for_difficulty`.
+// Both think for a fixed time, since the cost of a playout grows with the board.
+const SHORT_MCTS: &str = "MCTS (3 s)";
+const LONG_MCTS: &str = "MCTS (10 s)";
+/// A fixed playout count, for runs that must replay exactly from a seed.
+const FIXED_MCTS: &str = "MCTS (1000 playouts)";
+
+type AgentFactory = Box<dyn Fn(u64) -> Box<dyn GoAgent> + Send + Sync>;
+
+/// Named constructors for every agent the app can put in charge of a color.
+/// Each constructor receives the seed for the agent's RNG.
+#[derive(Resource)]
+pub struct AgentRegistry {
+    agents: Vec<(&'static str, AgentFactory)>,
+}
+
+impl Default for AgentRegistry {
+    fn default() -> Self {
+        let mut registry = Self { agents: Vec::new() };
+        registry
+            .register(RandomAgent::NAME, |seed| Box::new(RandomAgent::new(seed)))
+            .register(HeuristicAgent::NAME, |seed| {
+                Box::new(HeuristicAgent::new(seed))
+            })
+            .register(MctsAgent::NAME, |seed| {
+                Box::new(MctsAgent::with_default_budget(seed))
+            })
+            .register(SHORT_MCTS, |seed| {
+                Box::new(MctsAgent::new(
+                    SearchBudget::Time(Duration::from_secs(3)),
+                    seed,
+                ))
+            })
+            .register(LONG_MCTS, |seed| {
+                Box::new(MctsAgent::new(
+                    SearchBudget::Time(Duration::from_secs(10)),
+                    seed,
+                ))
+            })
+            .register(FIXED_MCTS, |seed| {
+                Box::new(MctsAgent::new(SearchBudget::Playouts(1000), seed))
+            });
+        registry
+    }
+}
+
+impl AgentRegistry {
+    /// The built-in agents plus the configured GTP engine, if any.
+    pub fn from_config(config: &configure::Config) -> Self {
+        let mut registry = Self::default();
+        if let Some(engine) = &config.gtp_engine {
+            let command = std::iter::once(engine)
+                .chain(&config.gtp_engine_args)
+                .cloned()
+                .collect::<Vec<_>>();
+            registry.register(GtpAgent::NAME, move |_| {
+                Box::new(GtpAgent::new(command.clone()))
+            });
+        }
+        registry
+    }
+
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        factory: impl Fn(u64) -> Box<dyn GoAgent> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.agents.retain(|(registered, _)| *registered != name);
+        self.agents.push((name, Box::new(factory)));
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.agents.iter().map(|(name, _)| *name)
+    }
+
+    pub fn create(&self, name: &str, seed: u64) -> Option<Box<dyn GoAgent>> {
+        self.agents
+            .iter()
+            .find(|(registered, _)| *registered == name)
+            .map(|(_, factory)| factory(seed))
+    }
+}
+
+/// Registry names of the agents chosen for each color.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectedAgents {
+    pub black: &'static str,
+    pub white: &'static str,
+}
+
+impl SelectedAgents {
+    /// Both colors played by the agent behind `difficulty`.
+    pub fn for_difficulty(difficulty: Difficulty) -> Self {
+        let name = match difficulty {
+            Difficulty::Easy => HeuristicAgent::NAME,
+            Difficulty::Normal => SHORT_MCTS,
+            Difficulty::Hard => LONG_MCTS,
+        };
+        Self {
+            black: name,
+            white: name,
+        }
+    }
+}
+
+type SharedAgent = Arc<Mutex<Box<dyn GoAgent>>>;
+
+/// A search running on the [`AsyncComputeTaskPool`]. Dropping the task alone
+/// would not stop a CPU-bound search, so dropping this also raises `cancel`.
+struct Search {
+    task: Task<Decision>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Drop for Search {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Agent instances playing the AI-controlled colors of the current game.
+#[derive(Resource, Default)]
+pub struct ActiveAgents {
+    black: Option<SharedAgent>,
+    white: Option<SharedAgent>,
+    search: Option<Search>,
+    replayable: bool,
+}
+
+impl ActiveAgents {
+    fn get(&self, stone: Stone) -> Option<&SharedAgent> {
+        match stone {
+            Stone::Black => self.black.as_ref(),
+            Stone::White => self.white.as_ref(),
+        }
+    }
+
+    pub fn controls(&self, stone: Stone) -> bool {
+        self.get(stone).is_some()
+    }
+
+    /// Whether replaying the game's seed reproduces every agent hand.
+    pub fn replayable(&self) -> bool {
+        self.replayable
+    }
+
+    /// Returns the decision for `turn` once its search has finished.
+    ///
+    /// The first call for a turn starts the search unless [`Self::start_search`]
+    /// already did, later calls only check whether it is done, so frames never
+    /// wait for an agent.
+    pub fn poll_hand(&mut self, game: &Game, turn: Stone) -> Option<Decision> {
+        let Some(search) = &mut self.search else {
+            self.start_search(game, turn);
+            return None;
+        };
+        let decision = block_on(poll_once(&mut search.task))?;
+        self.search = None;
+        Some(decision)
+    }
+
+    /// Starts searching `turn`'s hand on the [`AsyncComputeTaskPool`], unless
+    /// `turn` has no agent or a search is already running.
+    pub fn start_search(&mut self, game: &Game, turn: Stone) {
+        if self.search.is_some() {
+            return;
+        }
+        let Some(agent) = self.get(turn).cloned() else {
+            return;
+        };
+        let game = game.clone();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            agent
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .select_hand(&game, turn, &flag)
+        });
+        self.search = Some(Search { task, cancel });
+    }
+
+    /// Stops the running search, if any.
+    pub fn cancel_search(&mut self) {
+        self.search = None;
+    }
+}
+
+/// Creates the agents for a new game, seeding them from a freshly drawn [`GameSeed`].
+pub fn setup_agents(
+    mut commands: Commands,
+    registry: Res<AgentRegistry>,
+    controllers: Res<PlayerControllers>,
+    selected: Res<SelectedAgents>,
+    settings: Res<GameSettings>,
+    mut seed: ResMut<GameSeed>,
+) {
+    let seed = seed.next_game();
+    log::info!("Game seed: {seed}");
+    let mut seeds = StdRng::seed_from_u64(seed);
+    let mut replayable = true;
+    let mut create = |controller: Controller, name: &str| {
+        let agent_seed = seeds.random();
+        if controller != Controller::Ai {
+            return None;
+        }
+        let agent = registry.create(name, agent_seed);
+        if agent.is_none() {
+            log::warn!("Agent {name} is not registered");
+        }
+        agent.map(|mut agent| {
+            agent.set_komi(settings.komi);
+            replayable &= agent.replayable();
+            Arc::new(Mutex::new(agent))
+        })
+    };
+    log::info!(
+        "Agents: Black={:?}({}), White={:?}({})",
+        controllers.black,
+        selected.black,
+        controllers.white,
+        selected.white
+    );
+    let black = create(controllers.black, selected.black);
+    let white = create(controllers.white, selected.white);
+    if !replayable {
+        log::warn!(
+            "An agent searches on a time budget or runs externally; seed {seed} will not replay this game exactly"
+        );
+    }
+    commands.insert_resource(ActiveAgents {
+        black,
+        white,
+        search: None,
+        replayable,
+    });
+}
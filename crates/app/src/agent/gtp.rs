@@ -0,0 +1,209 @@
+use super::{Decision, GoAgent};
+use crate::{
+    gtp::{color_name, format_vertex, parse_vertex},
+    rules,
+};
+use igo_core::{Game, GameHand, Stone};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::atomic::AtomicBool,
+};
+
+fn same_board(a: &Game, b: &Game) -> bool {
+    let size = a.size();
+    size == b.size()
+        && (0..size)
+            .flat_map(|y| (0..size).map(move |x| (x, y)))
+            .all(|(x, y)| a.board().get_stone(x, y) == b.board().get_stone(x, y))
+}
+
+/// A running engine process talking GTP over its stdin and stdout.
+struct GtpEngine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl GtpEngine {
+    fn spawn(command: &[String]) -> io::Result<Self> {
+        let (program, args) = command.split_first().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "empty GTP engine command")
+        })?;
+        log::info!("Starting GTP engine: {}", command.join(" "));
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    /// Sends `command` and returns the text of a successful (`=`) response.
+    fn send(&mut self, command: &str) -> io::Result<String> {
+        log::trace!("GTP > {command}");
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()?;
+        // A response is one or more lines terminated by an empty line.
+        let mut response = String::new();
+        loop {
+            let mut line = String::new();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "GTP engine closed its output",
+                ));
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                if response.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            if !response.is_empty() {
+                response.push('\n');
+            }
+            response.push_str(line);
+        }
+        log::trace!("GTP < {response}");
+        match response.strip_prefix('=') {
+            Some(text) => Ok(text.trim().to_string()),
+            None => Err(io::Error::other(format!(
+                "GTP command `{command}` failed: {response}"
+            ))),
+        }
+    }
+}
+
+impl Drop for GtpEngine {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit").and_then(|_| self.stdin.flush());
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Lets an external engine, started as a child process, choose the hands.
+///
+/// The engine is started on the first search. Between searches only the
+/// opponent's reply is sent; any other difference (a new game, an undo)
+/// sets the whole position up again.
+pub struct GtpAgent {
+    command: Vec<String>,
+    komi: f32,
+    engine: Option<GtpEngine>,
+    /// Position the engine holds after its last hand.
+    synced: Option<Game>,
+}
+
+impl GtpAgent {
+    pub const NAME: &'static str = "GTP engine";
+
+    /// `command` is the engine's program followed by its arguments.
+    pub fn new(command: Vec<String>) -> Self {
+        Self {
+            command,
+            komi: rules::DEFAULT_KOMI,
+            engine: None,
+            synced: None,
+        }
+    }
+
+    fn genmove(&mut self, game: &Game, turn: Stone) -> io::Result<Decision> {
+        let engine = match &mut self.engine {
+            Some(engine) => engine,
+            engine @ None => engine.insert(GtpEngine::spawn(&self.command)?),
+        };
+        let size = game.size();
+        let opponent_hand = self.synced.as_ref().and_then(|synced| {
+            let board = (synced.board(), game.board());
+            let added = (0..size)
+                .flat_map(|y| (0..size).map(move |x| (x, y)))
+                .filter(|&(x, y)| board.0.get_stone(x, y).is_none())
+                .filter(|&(x, y)| board.1.get_stone(x, y) == Some(turn.opposite()))
+                .collect::<Vec<_>>();
+            let hand = match added[..] {
+                [] => GameHand::pass(turn.opposite()),
+                [(x, y)] => rules::find_hand(synced, turn.opposite(), x, y)?,
+                _ => return None,
+            };
+            let mut expected = synced.clone();
+            expected.put_hand(hand);
+            same_board(&expected, game).then_some(added.first().copied())
+        });
+        match opponent_hand {
+            Some(Some((x, y))) => {
+                let vertex = format_vertex(x, y, size);
+                engine.send(&format!("play {} {vertex}", color_name(turn.opposite())))?;
+            }
+            Some(None) => {
+                engine.send(&format!("play {} pass", color_name(turn.opposite())))?;
+            }
+            None => {
+                engine.send(&format!("boardsize {size}"))?;
+                engine.send("clear_board")?;
+                engine.send(&format!("komi {}", self.komi))?;
+                for y in 0..size {
+                    for x in 0..size {
+                        if let Some(stone) = game.board().get_stone(x, y) {
+                            let vertex = format_vertex(x, y, size);
+                            engine.send(&format!("play {} {vertex}", color_name(stone)))?;
+                        }
+                    }
+                }
+            }
+        }
+
+        let reply = engine
+            .send(&format!("genmove {}", color_name(turn)))?
+            .to_ascii_lowercase();
+        let hand = match reply.as_str() {
+            "pass" => GameHand::pass(turn),
+            "resign" => {
+                log::info!("GTP engine resigns");
+                self.synced = None;
+                return Ok(Decision::Resign);
+            }
+            vertex => parse_vertex(vertex, size)
+                .and_then(|(x, y)| rules::find_hand(game, turn, x, y))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("GTP engine played an illegal move: {vertex}"),
+                    )
+                })?,
+        };
+        let mut next = game.clone();
+        next.put_hand(hand);
+        self.synced = Some(next);
+        Ok(Decision::Play(hand))
+    }
+}
+
+impl GoAgent for GtpAgent {
+    fn select_hand(&mut self, game: &Game, turn: Stone, _cancel: &AtomicBool) -> Decision {
+        self.genmove(game, turn).unwrap_or_else(|err| {
+            log::error!("GTP engine failed, passing: {err}");
+            // Start over with a fresh process on the next search.
+            self.engine = None;
+            self.synced = None;
+            Decision::Play(GameHand::pass(turn))
+        })
+    }
+
+    fn set_komi(&mut self, komi: f32) {
+        self.komi = komi;
+        self.synced = None;
+    }
+
+    fn replayable(&self) -> bool {
+        false
+    }
+}
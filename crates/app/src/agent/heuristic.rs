@@ -0,0 +1,180 @@
+use super::{Decision, GoAgent};
+use crate::rules;
+use igo_core::{Game, GameHand, Stone};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::sync::atomic::AtomicBool;
+
+const CAPTURE_WEIGHT: f32 = 10.;
+const SAVE_WEIGHT: f32 = 8.;
+const ATARI_WEIGHT: f32 = 2.;
+const SELF_ATARI_WEIGHT: f32 = -6.;
+
+/// The hand of each color for every intersection of one board size, read
+/// once from an empty board so playouts can place stones by coordinates.
+pub struct HandTable {
+    size: u8,
+    black: Vec<GameHand>,
+    white: Vec<GameHand>,
+}
+
+impl HandTable {
+    pub fn new(size: u8) -> Self {
+        // Every intersection is allowed on an empty board.
+        let empty = Game::new(size);
+        let hands = |stone| {
+            let mut hands = vec![GameHand::pass(stone); size as usize * size as usize];
+            for hand in empty.get_allowed_hands(stone) {
+                if let Some((x, y)) = rules::hand_position(&empty, hand) {
+                    hands[y as usize * size as usize + x as usize] = hand;
+                }
+            }
+            hands
+        };
+        Self {
+            size,
+            black: hands(Stone::Black),
+            white: hands(Stone::White),
+        }
+    }
+
+    fn get(&self, stone: Stone, x: u8, y: u8) -> GameHand {
+        let hands = match stone {
+            Stone::Black => &self.black,
+            Stone::White => &self.white,
+        };
+        hands[y as usize * self.size as usize + x as usize]
+    }
+}
+
+/// Plays a random legal hand for `turn` that does not fill one of its own
+/// eyes and returns the resulting position. `None` means there is no such
+/// hand, so `turn` should pass.
+///
+/// Cheap enough to drive the playouts of stronger agents: random empty points
+/// are tried one at a time, each with a single `put_hand` on a scratch copy.
+pub fn playout_hand(
+    game: &Game,
+    turn: Stone,
+    hands: &HandTable,
+    rng: &mut impl Rng,
+) -> Option<Game> {
+    let size = game.size();
+    let board = game.board();
+    let mut points = (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .filter(|&(x, y)| board.get_stone(x, y).is_none())
+        .collect::<Vec<_>>();
+    while !points.is_empty() {
+        let (x, y) = points.swap_remove(rng.random_range(0..points.len()));
+        if rules::is_eye(game, x, y, turn) {
+            continue;
+        }
+        let mut next = game.clone();
+        next.put_hand(hands.get(turn, x, y));
+        // An illegal hand leaves the point empty.
+        if next.board().get_stone(x, y).is_some() {
+            return Some(next);
+        }
+    }
+    None
+}
+
+/// Rule-based agent: never fills its own eyes, prefers captures and saving
+/// groups in atari, avoids self-atari, and passes when only bad hands remain.
+#[derive(Debug)]
+pub struct HeuristicAgent {
+    rng: StdRng,
+}
+
+impl HeuristicAgent {
+    pub const NAME: &'static str = "Heuristic";
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Scores playing `hand` at `(x, y)`, or `None` when it fills an own eye.
+    fn evaluate(game: &Game, turn: Stone, hand: GameHand, (x, y): (u8, u8)) -> Option<f32> {
+        if rules::is_eye(game, x, y, turn) {
+            return None;
+        }
+        let size = game.size();
+        let mut next = game.clone();
+        next.put_hand(hand);
+        let captured = rules::count_stones(game, turn.opposite())
+            - rules::count_stones(&next, turn.opposite());
+        let mut score = captured as f32 * CAPTURE_WEIGHT;
+
+        let placed = rules::group_at(&next, x, y)?;
+        let rescued = rules::neighbors(x, y, size)
+            .filter_map(|(nx, ny)| rules::group_at(game, nx, ny))
+            .filter(|group| group.stone == turn && group.liberties.len() == 1)
+            .map(|group| group.stones.len())
+            .max()
+            .unwrap_or(0);
+        if placed.liberties.len() >= 2 {
+            score += rescued as f32 * SAVE_WEIGHT;
+        } else if captured == 0 {
+            score += placed.stones.len() as f32 * SELF_ATARI_WEIGHT;
+        }
+
+        let ataris = rules::neighbors(x, y, size)
+            .filter_map(|(nx, ny)| rules::group_at(&next, nx, ny))
+            .filter(|group| group.stone == turn.opposite() && group.liberties.len() == 1)
+            .count();
+        score += ataris as f32 * ATARI_WEIGHT;
+        Some(score)
+    }
+}
+
+impl GoAgent for HeuristicAgent {
+    fn select_hand(&mut self, game: &Game, turn: Stone, _cancel: &AtomicBool) -> Decision {
+        let rng = &mut self.rng;
+        let hand = game
+            .get_allowed_hands(turn)
+            .iter()
+            .filter_map(|&hand| {
+                let pos = rules::hand_position(game, hand)?;
+                let score = Self::evaluate(game, turn, hand, pos)?;
+                // Jitter breaks ties so equal hands are not always played in board order.
+                Some((hand, score + rng.random::<f32>()))
+            })
+            .filter(|(_, score)| *score >= 0.)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or(GameHand::pass(turn), |(hand, _)| hand);
+        Decision::Play(hand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hand_table_covers_every_intersection() {
+        let table = HandTable::new(9);
+        let empty = Game::new(9);
+        for (x, y) in (0..9).flat_map(|y| (0..9).map(move |x| (x, y))) {
+            for stone in [Stone::Black, Stone::White] {
+                let hand = table.get(stone, x, y);
+                assert_eq!(rules::hand_position(&empty, hand), Some((x, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn playout_hand_keeps_own_eyes() {
+        // Black fills the 3x3 board except for three single-point eyes.
+        let mut game = Game::new(3);
+        for (x, y) in [(1, 0), (0, 1), (1, 1), (2, 1), (0, 2), (1, 2)] {
+            game.put_hand(rules::find_hand(&game, Stone::Black, x, y).unwrap());
+        }
+        let table = HandTable::new(3);
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(playout_hand(&game, Stone::Black, &table, &mut rng).is_none());
+        let next = playout_hand(&Game::new(3), Stone::White, &table, &mut rng).unwrap();
+        assert_eq!(rules::count_stones(&next, Stone::White), 1);
+    }
+}
@@ -0,0 +1,319 @@
+use super::{
+    Decision, GoAgent,
+    heuristic::{self, HandTable},
+};
+use crate::rules;
+use bevy::platform::time::Instant;
+use igo_core::{Game, GameHand, Stone};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+const DEFAULT_THINKING_TIME: Duration = Duration::from_secs(2);
+
+/// How long a single [`MctsAgent`] search may run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchBudget {
+    Playouts(u32),
+    Time(Duration),
+}
+
+struct Node {
+    hand: Option<GameHand>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Hands not expanded yet, flagged `true` for the pass.
+    untried: Vec<(GameHand, bool)>,
+    /// Color that played `hand`; wins are counted from its point of view.
+    played_by: Stone,
+    /// Consecutive passes leading to this node, two means the game is over.
+    passes: u8,
+    visits: u32,
+    wins: f32,
+}
+
+impl Node {
+    fn new(hand: Option<GameHand>, parent: Option<usize>, played_by: Stone, passes: u8) -> Self {
+        Self {
+            hand,
+            parent,
+            children: Vec::new(),
+            untried: Vec::new(),
+            played_by,
+            passes,
+            visits: 0,
+            wins: 0.,
+        }
+    }
+
+    fn uct(&self, parent_visits: u32, exploration: f32) -> f32 {
+        let visits = self.visits.max(1) as f32;
+        self.wins / visits + exploration * ((parent_visits.max(1) as f32).ln() / visits).sqrt()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SearchParams {
+    komi: f32,
+    exploration: f32,
+}
+
+/// Monte Carlo Tree Search over eye-preserving random `igo_core::Game` playouts.
+pub struct MctsAgent {
+    budget: SearchBudget,
+    params: SearchParams,
+    rng: StdRng,
+}
+
+impl MctsAgent {
+    pub const NAME: &'static str = "MCTS";
+
+    pub fn new(budget: SearchBudget, seed: u64) -> Self {
+        Self {
+            budget,
+            params: SearchParams::new(rules::DEFAULT_KOMI),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Thinks for a couple of seconds per hand.
+    pub fn with_default_budget(seed: u64) -> Self {
+        Self::new(SearchBudget::Time(DEFAULT_THINKING_TIME), seed)
+    }
+}
+
+impl SearchParams {
+    fn new(komi: f32) -> Self {
+        Self {
+            komi,
+            exploration: std::f32::consts::SQRT_2,
+        }
+    }
+
+    /// A tree holding only the root, with `turn` to move on `game`.
+    fn new_tree(game: &Game, turn: Stone) -> Vec<Node> {
+        let mut root = Node::new(None, None, turn.opposite(), 0);
+        root.untried = Self::candidates(game, turn);
+        vec![root]
+    }
+
+    fn candidates(game: &Game, turn: Stone) -> Vec<(GameHand, bool)> {
+        let mut hands = game
+            .get_allowed_hands(turn)
+            .iter()
+            .map(|&hand| (hand, false))
+            .collect::<Vec<_>>();
+        hands.push((GameHand::pass(turn), true));
+        hands
+    }
+
+    /// Plays random hands that keep each side's eyes until both sides pass or
+    /// the move limit is hit, and returns the color ahead on area score.
+    fn playout(
+        &self,
+        mut game: Game,
+        mut turn: Stone,
+        mut passes: u8,
+        hands: &HandTable,
+        rng: &mut impl Rng,
+    ) -> Stone {
+        let limit = game.size() as usize * game.size() as usize * 2;
+        for _ in 0..limit {
+            if passes >= 2 {
+                break;
+            }
+            match heuristic::playout_hand(&game, turn, hands, rng) {
+                Some(next) => {
+                    game = next;
+                    passes = 0;
+                }
+                None => {
+                    game.put_hand(GameHand::pass(turn));
+                    passes += 1;
+                }
+            }
+            turn = turn.opposite();
+        }
+        let (black, white) = rules::area_score(&game);
+        if black as f32 - white as f32 > self.komi {
+            Stone::Black
+        } else {
+            Stone::White
+        }
+    }
+
+    fn iterate(
+        &self,
+        nodes: &mut Vec<Node>,
+        root_game: &Game,
+        hands: &HandTable,
+        rng: &mut impl Rng,
+    ) {
+        let mut game = root_game.clone();
+        let mut idx = 0;
+        // Selection
+        while nodes[idx].untried.is_empty() && !nodes[idx].children.is_empty() {
+            let parent_visits = nodes[idx].visits;
+            idx = nodes[idx]
+                .children
+                .iter()
+                .copied()
+                .max_by(|&a, &b| {
+                    nodes[a]
+                        .uct(parent_visits, self.exploration)
+                        .total_cmp(&nodes[b].uct(parent_visits, self.exploration))
+                })
+                .unwrap_or(idx);
+            if let Some(hand) = nodes[idx].hand {
+                game.put_hand(hand);
+            }
+        }
+        // Expansion
+        let mut turn = nodes[idx].played_by.opposite();
+        if nodes[idx].passes < 2 && !nodes[idx].untried.is_empty() {
+            let pick = rng.random_range(0..nodes[idx].untried.len());
+            let (hand, is_pass) = nodes[idx].untried.swap_remove(pick);
+            let passes = if is_pass { nodes[idx].passes + 1 } else { 0 };
+            game.put_hand(hand);
+            let mut child = Node::new(Some(hand), Some(idx), turn, passes);
+            if passes < 2 {
+                child.untried = Self::candidates(&game, turn.opposite());
+            }
+            nodes.push(child);
+            let child_idx = nodes.len() - 1;
+            nodes[idx].children.push(child_idx);
+            idx = child_idx;
+            turn = turn.opposite();
+        }
+        // Simulation
+        let winner = self.playout(game, turn, nodes[idx].passes, hands, rng);
+        // Backpropagation
+        let mut current = Some(idx);
+        while let Some(i) = current {
+            nodes[i].visits += 1;
+            if nodes[i].played_by == winner {
+                nodes[i].wins += 1.;
+            }
+            current = nodes[i].parent;
+        }
+    }
+}
+
+impl GoAgent for MctsAgent {
+    fn select_hand(&mut self, game: &Game, turn: Stone, cancel: &AtomicBool) -> Decision {
+        // A cancelled search gives its random draws back, so replaying a seed
+        // after an undo makes the same choices as the original game.
+        let rng = self.rng.clone();
+        let mut nodes = SearchParams::new_tree(game, turn);
+        let hands = HandTable::new(game.size());
+        let started = Instant::now();
+        let mut playouts = 0;
+        loop {
+            let done = match self.budget {
+                SearchBudget::Playouts(limit) => playouts >= limit,
+                SearchBudget::Time(limit) => started.elapsed() >= limit,
+            };
+            if cancel.load(Ordering::Relaxed) {
+                log::debug!("MCTS: cancelled after {playouts} playouts");
+                self.rng = rng;
+                return Decision::Play(GameHand::pass(turn));
+            }
+            if done {
+                break;
+            }
+            self.params.iterate(&mut nodes, game, &hands, &mut self.rng);
+            playouts += 1;
+        }
+        let best = nodes[0]
+            .children
+            .iter()
+            .max_by_key(|&&child| nodes[child].visits)
+            .and_then(|&child| nodes[child].hand);
+        log::debug!(
+            "MCTS: {playouts} playouts in {:?}, {} nodes",
+            started.elapsed(),
+            nodes.len()
+        );
+        Decision::Play(best.unwrap_or(GameHand::pass(turn)))
+    }
+
+    fn set_komi(&mut self, komi: f32) {
+        self.params.komi = komi;
+    }
+
+    fn replayable(&self) -> bool {
+        // A time budget fits as many playouts as the machine's load allows.
+        matches!(self.budget, SearchBudget::Playouts(_))
+    }
+}
+
+/// A root hand considered by an [`Analysis`].
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate {
+    pub hand: GameHand,
+    pub visits: u32,
+    /// Share of playouts through this hand won by the side to move.
+    pub win_rate: f32,
+}
+
+/// An MCTS tree that is deepened step by step so its candidates can be
+/// inspected while the search goes on.
+pub struct Analysis {
+    game: Game,
+    nodes: Vec<Node>,
+    params: SearchParams,
+    hands: HandTable,
+    rng: StdRng,
+}
+
+impl Analysis {
+    pub fn new(game: &Game, turn: Stone, komi: f32, seed: u64) -> Self {
+        Self {
+            game: game.clone(),
+            nodes: SearchParams::new_tree(game, turn),
+            params: SearchParams::new(komi),
+            hands: HandTable::new(game.size()),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Keeps iterating for `duration`, stopping after the current playout
+    /// once `cancel` is set.
+    pub fn search(&mut self, duration: Duration, cancel: &AtomicBool) {
+        let started = Instant::now();
+        while started.elapsed() < duration && !cancel.load(Ordering::Relaxed) {
+            self.params
+                .iterate(&mut self.nodes, &self.game, &self.hands, &mut self.rng);
+        }
+    }
+
+    pub fn playouts(&self) -> u32 {
+        self.nodes[0].visits
+    }
+
+    /// Size of the search tree.
+    pub fn nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The `count` most visited root hands, best first.
+    pub fn candidates(&self, count: usize) -> Vec<Candidate> {
+        let mut candidates = self.nodes[0]
+            .children
+            .iter()
+            .filter_map(|&child| {
+                let node = &self.nodes[child];
+                Some(Candidate {
+                    hand: node.hand?,
+                    visits: node.visits,
+                    win_rate: node.wins / node.visits.max(1) as f32,
+                })
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.visits));
+        candidates.truncate(count);
+        candidates
+    }
+}
@@ -0,0 +1,31 @@
+use super::{Decision, GoAgent};
+use igo_core::{Game, GameHand, Stone};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::sync::atomic::AtomicBool;
+
+/// Plays a uniformly random allowed hand, passing only when nothing is allowed.
+#[derive(Debug)]
+pub struct RandomAgent {
+    rng: StdRng,
+}
+
+impl RandomAgent {
+    pub const NAME: &'static str = "Random";
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl GoAgent for RandomAgent {
+    fn select_hand(&mut self, game: &Game, turn: Stone, _cancel: &AtomicBool) -> Decision {
+        let hands = game.get_allowed_hands(turn);
+        Decision::Play(if hands.is_empty() {
+            GameHand::pass(turn)
+        } else {
+            hands[self.rng.random_range(0..hands.len())]
+        })
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    AppState, GameSeed, GameState, GoGameResource,
+    agent::{Analysis, Candidate},
+    in_game::BoardLayout,
+    rules,
+};
+use bevy::{platform::time::Instant, prelude::*, tasks::AsyncComputeTaskPool};
+use std::{
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+/// Candidates drawn on the board.
+const CANDIDATE_COUNT: usize = 5;
+/// Search time between two published snapshots.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(250);
+/// The search stops deepening after this many playouts.
+const MAX_PLAYOUTS: u32 = 200_000;
+/// Tree size at which the search stops; every node keeps its untried hands,
+/// which on 19x19 is a few kilobytes.
+const MAX_NODES: usize = 20_000;
+/// Longest a single position is analysed.
+const MAX_SEARCH_TIME: Duration = Duration::from_secs(60);
+
+/// Whether the analysis overlay is on.
+#[derive(Resource, Debug, Default)]
+pub struct ShowAnalysis(pub bool);
+
+/// Latest results of the background search; `revision` changes on every update.
+#[derive(Debug, Default)]
+struct Snapshot {
+    revision: u64,
+    playouts: u32,
+    candidates: Vec<((u8, u8), Candidate)>,
+}
+
+/// Handle to the background search over the current position.
+#[derive(Resource, Default)]
+pub struct AnalysisSearch {
+    cancel: Option<Arc<AtomicBool>>,
+    snapshot: Arc<Mutex<Snapshot>>,
+}
+
+impl AnalysisSearch {
+    /// Stops the running search and clears its results.
+    fn stop(&mut self) {
+        // The flag is raised before the lock is taken, and the search only
+        // publishes while holding the lock, so nothing stale is written after this.
+        if let Some(cancel) = self.cancel.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        let mut snapshot = self.snapshot.lock().unwrap_or_else(PoisonError::into_inner);
+        snapshot.revision += 1;
+        snapshot.playouts = 0;
+        snapshot.candidates.clear();
+    }
+}
+
+#[derive(Component)]
+pub struct AnalysisMarker;
+
+pub fn toggle_analysis(input: Res<ButtonInput<KeyCode>>, mut show: ResMut<ShowAnalysis>) {
+    if input.just_pressed(KeyCode::KeyH) {
+        show.0 = !show.0;
+        log::debug!("Analysis: {}", show.0);
+    }
+}
+
+/// Starts a fresh search on the [`AsyncComputeTaskPool`] whenever the position
+/// changes, so `Update` only ever reads the latest snapshot. Only a running
+/// game is analysed; pausing or ending it stops the search.
+pub fn restart_analysis(
+    game: Res<GoGameResource>,
+    show: Res<ShowAnalysis>,
+    seed: Res<GameSeed>,
+    state: Res<State<GameState>>,
+    mut search: ResMut<AnalysisSearch>,
+) {
+    search.stop();
+    if !show.0 || *state.get() != GameState::Running {
+        return;
+    }
+    log::trace!("Restarting analysis");
+    let cancel = Arc::new(AtomicBool::new(false));
+    search.cancel = Some(cancel.clone());
+    let snapshot = search.snapshot.clone();
+    let position = game.game.clone();
+    let mut analysis = Analysis::new(
+        &position,
+        game.turn,
+        game.komi,
+        seed.current.wrapping_add(game.moves.len() as u64),
+    );
+    AsyncComputeTaskPool::get()
+        .spawn(async move {
+            let started = Instant::now();
+            while !cancel.load(Ordering::Relaxed)
+                && analysis.playouts() < MAX_PLAYOUTS
+                && analysis.nodes() < MAX_NODES
+                && started.elapsed() < MAX_SEARCH_TIME
+            {
+                analysis.search(SNAPSHOT_INTERVAL, &cancel);
+                // The pass has no intersection to draw on, so ask for one extra.
+                let candidates = analysis
+                    .candidates(CANDIDATE_COUNT + 1)
+                    .into_iter()
+                    .filter_map(|candidate| {
+                        Some((rules::hand_position(&position, candidate.hand)?, candidate))
+                    })
+                    .take(CANDIDATE_COUNT)
+                    .collect();
+                let mut snapshot = snapshot.lock().unwrap_or_else(PoisonError::into_inner);
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                snapshot.revision += 1;
+                snapshot.playouts = analysis.playouts();
+                snapshot.candidates = candidates;
+            }
+        })
+        .detach();
+}
+
+pub fn stop_analysis(mut search: ResMut<AnalysisSearch>) {
+    search.stop();
+}
+
+/// Redraws the candidate markers when the search has published new results.
+pub fn draw_analysis(
+    mut commands: Commands,
+    search: Res<AnalysisSearch>,
+    layout: Res<BoardLayout>,
+    markers: Query<Entity, With<AnalysisMarker>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut drawn_revision: Local<u64>,
+) {
+    use bevy::color::palettes::tailwind::*;
+    let Ok(snapshot) = search.snapshot.try_lock() else {
+        return;
+    };
+    if snapshot.revision == *drawn_revision {
+        return;
+    }
+    *drawn_revision = snapshot.revision;
+    log::trace!("Analysis: {} playouts", snapshot.playouts);
+    for entity in &markers {
+        commands.entity(entity).despawn();
+    }
+    let circle = meshes.add(Circle::new(layout.stone_radius));
+    let best_material = materials.add(ColorMaterial::from(Color::Srgba(GREEN_500.with_alpha(0.6))));
+    let material = materials.add(ColorMaterial::from(Color::Srgba(SKY_500.with_alpha(0.6))));
+    let font = TextFont {
+        font_size: layout.stone_radius * 0.6,
+        ..Default::default()
+    };
+    for (rank, ((x, y), candidate)) in snapshot.candidates.iter().enumerate() {
+        commands
+            .spawn((
+                DespawnOnExit(AppState::InGame),
+                AnalysisMarker,
+                Mesh2d(circle.clone()),
+                MeshMaterial2d(if rank == 0 {
+                    best_material.clone()
+                } else {
+                    material.clone()
+                }),
+                Transform::from_translation(layout.position(*x, *y).extend(3.)),
+            ))
+            .with_children(|p| {
+                p.spawn((
+                    Text2d::new(format!(
+                        "{}\n{:.0}%",
+                        candidate.visits,
+                        candidate.win_rate * 100.
+                    )),
+                    TextColor(Color::WHITE),
+                    TextLayout {
+                        justify: Justify::Center,
+                        ..Default::default()
+                    },
+                    font.clone(),
+                    Transform::from_xyz(0., 0., 1.),
+                ));
+            });
+    }
+}
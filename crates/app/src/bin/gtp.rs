@@ -0,0 +1,3 @@
+fn main() {
+    my_rougue::gtp::run();
+}
@@ -0,0 +1,3 @@
+fn main() {
+    my_rougue::tournament::run();
+}
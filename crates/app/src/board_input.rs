@@ -0,0 +1,119 @@
+use crate::{
+    Controller, GameState, GoGameResource, PlayerControllers,
+    illegal_move::IllegalMoveAttempt,
+    in_game::{BoardLayout, StonePos},
+    rules,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use std::collections::HashMap;
+
+/// Maps the window cursor through the 2D camera onto the board intersections.
+#[derive(SystemParam)]
+pub struct BoardCursor<'w, 's> {
+    window: Single<'w, 's, &'static Window>,
+    camera: Single<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    stones: Query<'w, 's, (&'static StonePos, &'static GlobalTransform)>,
+    layout: Option<Res<'w, BoardLayout>>,
+}
+
+impl BoardCursor<'_, '_> {
+    /// Cursor position in window coordinates, as used by UI nodes.
+    pub fn window_position(&self) -> Option<Vec2> {
+        self.window.cursor_position()
+    }
+
+    /// Intersection whose stone circle is under the cursor, if any.
+    pub fn intersection(&self) -> Option<(u8, u8)> {
+        let layout = self.layout.as_ref()?;
+        let cursor = self.window.cursor_position()?;
+        let (camera, camera_transform) = *self.camera;
+        let world = camera.viewport_to_world_2d(camera_transform, cursor).ok()?;
+        self.stones
+            .iter()
+            .map(|(pos, transform)| (pos, transform.translation().truncate().distance(world)))
+            .filter(|(_, distance)| *distance <= layout.stone_radius)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(pos, _)| (pos.x, pos.y))
+    }
+}
+
+pub fn place_stone_on_click(
+    mouse: Res<ButtonInput<MouseButton>>,
+    cursor: BoardCursor,
+    mut game: ResMut<GoGameResource>,
+    controllers: Res<PlayerControllers>,
+    mut state: ResMut<NextState<GameState>>,
+    mut illegal: MessageWriter<IllegalMoveAttempt>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) || controllers.get(game.turn) != Controller::Human {
+        return;
+    }
+    let Some((x, y)) = cursor.intersection() else {
+        return;
+    };
+    log::trace!("Clicked intersection ({x}, {y})");
+    let Some(hand) = rules::find_hand(&game.game, game.turn, x, y) else {
+        let reason = rules::illegal_reason(&game.game, game.turn, x, y);
+        log::debug!("Hand at ({x}, {y}) is not allowed: {reason}");
+        illegal.write(IllegalMoveAttempt { x, y, reason });
+        return;
+    };
+    if !game.play(hand) {
+        state.set(GameState::Scoring);
+    }
+}
+
+/// Alpha of the preview stone drawn under the cursor.
+const GHOST_ALPHA: f32 = 0.4;
+
+/// Shows a translucent stone of the side to move on the hovered intersection
+/// when a human may play there. Runs after `update_in_game`, which hides
+/// every empty point again each frame.
+pub fn preview_ghost_stone(
+    cursor: BoardCursor,
+    game: Res<GoGameResource>,
+    controllers: Res<PlayerControllers>,
+    mut stones: Query<(
+        &StonePos,
+        &mut MeshMaterial2d<ColorMaterial>,
+        &mut Visibility,
+    )>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut ghost_materials: Local<Option<[Handle<ColorMaterial>; 2]>>,
+    mut legal: Local<HashMap<(u8, u8), bool>>,
+) {
+    if game.is_changed() {
+        legal.clear();
+    }
+    if controllers.get(game.turn) != Controller::Human {
+        return;
+    }
+    let Some((x, y)) = cursor.intersection() else {
+        return;
+    };
+    if game.game.board().get_stone(x, y).is_some()
+        || !*legal
+            .entry((x, y))
+            .or_insert_with(|| rules::find_hand(&game.game, game.turn, x, y).is_some())
+    {
+        return;
+    }
+    let [black, white] = ghost_materials.get_or_insert_with(|| {
+        [
+            Color::srgba(0., 0., 0., GHOST_ALPHA),
+            Color::srgba(1., 1., 1., GHOST_ALPHA),
+        ]
+        .map(|color| materials.add(ColorMaterial::from(color)))
+    });
+    let ghost = match game.turn {
+        igo_core::Stone::Black => black.clone(),
+        igo_core::Stone::White => white.clone(),
+    };
+    if let Some((_, mut material, mut visibility)) = stones
+        .iter_mut()
+        .find(|(pos, _, _)| (pos.x, pos.y) == (x, y))
+    {
+        *material = MeshMaterial2d(ghost);
+        *visibility = Visibility::Visible;
+    }
+}
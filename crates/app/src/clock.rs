@@ -0,0 +1,392 @@
+use crate::{AppState, GameEnd, GameSettings, GameState, GoGameResource};
+use bevy::prelude::*;
+use igo_core::Stone;
+use std::time::Duration;
+
+/// How much thinking time each player gets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeControl {
+    Unlimited,
+    /// A single main time for the whole game.
+    Absolute {
+        main: Duration,
+    },
+    /// After the main time, every hand must be played within one period;
+    /// each overrun uses up one of the periods.
+    ByoYomi {
+        main: Duration,
+        period: Duration,
+        periods: u8,
+    },
+    /// Main time plus `increment` added after every hand.
+    Fischer {
+        main: Duration,
+        increment: Duration,
+    },
+}
+
+impl TimeControl {
+    fn main_time(&self) -> Duration {
+        match *self {
+            TimeControl::Unlimited => Duration::ZERO,
+            TimeControl::Absolute { main }
+            | TimeControl::ByoYomi { main, .. }
+            | TimeControl::Fischer { main, .. } => main,
+        }
+    }
+}
+
+impl std::fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let minutes = |duration: Duration| duration.as_secs() / 60;
+        match *self {
+            TimeControl::Unlimited => write!(f, "Unlimited"),
+            TimeControl::Absolute { main } => write!(f, "{} min", minutes(main)),
+            TimeControl::ByoYomi {
+                main,
+                period,
+                periods,
+            } => write!(f, "{} min + {periods}x{}s", minutes(main), period.as_secs()),
+            TimeControl::Fischer { main, increment } => {
+                write!(f, "{} min + {}s/move", minutes(main), increment.as_secs())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PlayerClock {
+    main: Duration,
+    period_left: Duration,
+    periods: u8,
+}
+
+impl PlayerClock {
+    fn new(control: &TimeControl) -> Self {
+        let (period_left, periods) = match *control {
+            TimeControl::ByoYomi {
+                period, periods, ..
+            } => (period, periods),
+            _ => (Duration::ZERO, 0),
+        };
+        Self {
+            main: control.main_time(),
+            period_left,
+            periods,
+        }
+    }
+
+    /// Spends `elapsed` and returns `true` when the flag falls.
+    fn tick(&mut self, control: &TimeControl, elapsed: Duration) -> bool {
+        let from_main = elapsed.min(self.main);
+        self.main -= from_main;
+        let mut overrun = elapsed - from_main;
+        if overrun.is_zero() {
+            return false;
+        }
+        match *control {
+            TimeControl::Unlimited => false,
+            TimeControl::ByoYomi { period, .. } => {
+                while !overrun.is_zero() && self.periods > 0 {
+                    let used = overrun.min(self.period_left);
+                    self.period_left -= used;
+                    overrun -= used;
+                    if self.period_left.is_zero() {
+                        self.periods -= 1;
+                        self.period_left = period;
+                    }
+                }
+                self.periods == 0
+            }
+            TimeControl::Absolute { .. } | TimeControl::Fischer { .. } => true,
+        }
+    }
+
+    /// Time left, ordered so that a smaller value means less thinking time.
+    fn remaining(&self) -> (Duration, u8, Duration) {
+        (self.main, self.periods, self.period_left)
+    }
+
+    /// Applies the time control after the player has finished a hand.
+    fn on_hand(&mut self, control: &TimeControl) {
+        match *control {
+            TimeControl::Fischer { increment, .. } => self.main += increment,
+            TimeControl::ByoYomi { period, .. } if self.main.is_zero() => self.period_left = period,
+            _ => {}
+        }
+    }
+
+    fn describe(&self, control: &TimeControl) -> String {
+        let clock = |duration: Duration| {
+            let secs = duration.as_secs();
+            format!("{:02}:{:02}", secs / 60, secs % 60)
+        };
+        match control {
+            TimeControl::Unlimited => "--:--".into(),
+            TimeControl::ByoYomi { .. } if self.main.is_zero() => {
+                format!("{} ({})", clock(self.period_left), self.periods)
+            }
+            _ => clock(self.main),
+        }
+    }
+}
+
+/// Remaining time of both players.
+#[derive(Resource, Debug, Clone)]
+pub struct GameClock {
+    control: TimeControl,
+    black: PlayerClock,
+    white: PlayerClock,
+    /// Both clocks, as `(black, white)`, right before the time control was
+    /// applied for each hand of the move list.
+    before_hand: Vec<(PlayerClock, PlayerClock)>,
+}
+
+impl GameClock {
+    pub fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            black: PlayerClock::new(&control),
+            white: PlayerClock::new(&control),
+            before_hand: Vec::new(),
+        }
+    }
+
+    /// Catches up with a move list of `moves` hands that alternate from
+    /// `first_turn`. New hands get the time control applied once; undone hands
+    /// take back what they were granted, without refunding time spent since,
+    /// so redoing them never grants it twice.
+    fn sync_hands(&mut self, moves: usize, first_turn: Stone) {
+        if let Some(&(black, white)) = self.before_hand.get(moves) {
+            let least = |now: PlayerClock, then: PlayerClock| {
+                if then.remaining() < now.remaining() {
+                    then
+                } else {
+                    now
+                }
+            };
+            self.black = least(self.black, black);
+            self.white = least(self.white, white);
+            self.before_hand.truncate(moves);
+        }
+        let control = self.control;
+        for index in self.before_hand.len()..moves {
+            self.before_hand.push((self.black, self.white));
+            let player = if index % 2 == 0 {
+                first_turn
+            } else {
+                first_turn.opposite()
+            };
+            self.get_mut(player).on_hand(&control);
+        }
+    }
+
+    fn get_mut(&mut self, stone: Stone) -> &mut PlayerClock {
+        match stone {
+            Stone::Black => &mut self.black,
+            Stone::White => &mut self.white,
+        }
+    }
+
+    pub fn describe(&self, stone: Stone) -> String {
+        match stone {
+            Stone::Black => self.black.describe(&self.control),
+            Stone::White => self.white.describe(&self.control),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ClockText(Stone);
+
+pub fn setup_clock(mut commands: Commands, settings: Res<GameSettings>) {
+    log::debug!("Time control: {}", settings.time_control);
+    commands.insert_resource(GameClock::new(settings.time_control));
+}
+
+pub fn setup_clock_ui(mut commands: Commands) {
+    log::trace!("Setting up clock UI");
+    commands
+        .spawn((
+            DespawnOnExit(AppState::InGame),
+            Node {
+                position_type: PositionType::Absolute,
+                left: px(40.0),
+                top: px(40.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: px(10.0),
+                ..Default::default()
+            },
+        ))
+        .with_children(|p| {
+            for stone in [Stone::Black, Stone::White] {
+                p.spawn((
+                    ClockText(stone),
+                    Text::default(),
+                    TextColor(Color::WHITE),
+                    TextFont {
+                        font_size: 30.0,
+                        ..Default::default()
+                    },
+                ));
+            }
+        });
+}
+
+/// Runs the clock of the side to move; only scheduled while the game is running,
+/// and frozen while the window is out of focus.
+pub fn tick_clock(
+    time: Res<Time>,
+    window: Single<&Window>,
+    mut clock: ResMut<GameClock>,
+    mut game: ResMut<GoGameResource>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    clock.sync_hands(game.moves.len(), game.first_turn);
+    if !window.focused {
+        return;
+    }
+    let control = clock.control;
+    let turn = game.turn;
+    if clock.get_mut(turn).tick(&control, time.delta()) {
+        log::info!("{turn:?} ran out of time");
+        game.end = Some(GameEnd::TimedOut(turn));
+        state.set(GameState::GameOver);
+    }
+}
+
+pub fn update_clock_text(clock: Res<GameClock>, mut texts: Query<(&ClockText, &mut Text)>) {
+    for (ClockText(stone), mut text) in &mut texts {
+        text.0 = format!("{stone:?}: {}", clock.describe(*stone));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BYO_YOMI: TimeControl = TimeControl::ByoYomi {
+        main: Duration::from_secs(10),
+        period: Duration::from_secs(5),
+        periods: 2,
+    };
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn absolute_flag_falls_after_main_time() {
+        let control = TimeControl::Absolute { main: secs(10) };
+        let mut clock = PlayerClock::new(&control);
+        assert!(!clock.tick(&control, secs(10)));
+        clock.on_hand(&control);
+        assert!(clock.tick(&control, secs(1)));
+    }
+
+    #[test]
+    fn unlimited_never_flags() {
+        let control = TimeControl::Unlimited;
+        let mut clock = PlayerClock::new(&control);
+        assert!(!clock.tick(&control, secs(100_000)));
+    }
+
+    #[test]
+    fn fischer_adds_increment_per_hand() {
+        let control = TimeControl::Fischer {
+            main: secs(10),
+            increment: secs(3),
+        };
+        let mut clock = PlayerClock::new(&control);
+        assert!(!clock.tick(&control, secs(8)));
+        clock.on_hand(&control);
+        assert_eq!(clock.main, secs(5));
+        assert!(!clock.tick(&control, secs(5)));
+        assert!(clock.tick(&control, secs(1)));
+    }
+
+    #[test]
+    fn byo_yomi_period_resets_after_a_hand() {
+        let mut clock = PlayerClock::new(&BYO_YOMI);
+        assert!(!clock.tick(&BYO_YOMI, secs(13)));
+        assert_eq!(
+            (clock.main, clock.period_left, clock.periods),
+            (secs(0), secs(2), 2)
+        );
+        clock.on_hand(&BYO_YOMI);
+        assert_eq!((clock.period_left, clock.periods), (secs(5), 2));
+    }
+
+    #[test]
+    fn byo_yomi_overrun_mid_period_uses_one_period() {
+        let mut clock = PlayerClock::new(&BYO_YOMI);
+        assert!(!clock.tick(&BYO_YOMI, secs(12)));
+        // 3 s left in the first period; overrunning it by 1 s starts the last one.
+        assert!(!clock.tick(&BYO_YOMI, secs(4)));
+        assert_eq!((clock.period_left, clock.periods), (secs(4), 1));
+        clock.on_hand(&BYO_YOMI);
+        assert_eq!((clock.period_left, clock.periods), (secs(5), 1));
+    }
+
+    #[test]
+    fn byo_yomi_flag_falls_when_last_period_expires() {
+        let mut clock = PlayerClock::new(&BYO_YOMI);
+        assert!(!clock.tick(&BYO_YOMI, secs(15)));
+        assert_eq!(clock.periods, 1);
+        assert!(!clock.tick(&BYO_YOMI, secs(4)));
+        assert!(clock.tick(&BYO_YOMI, secs(1)));
+        assert_eq!(clock.periods, 0);
+    }
+
+    #[test]
+    fn main_time_does_not_reset_periods() {
+        let mut clock = PlayerClock::new(&BYO_YOMI);
+        assert!(!clock.tick(&BYO_YOMI, secs(4)));
+        clock.on_hand(&BYO_YOMI);
+        assert_eq!((clock.main, clock.period_left), (secs(6), secs(5)));
+    }
+
+    #[test]
+    fn undo_and_redo_grant_the_increment_once() {
+        let control = TimeControl::Fischer {
+            main: secs(10),
+            increment: secs(5),
+        };
+        let mut clock = GameClock::new(control);
+        assert!(!clock.black.tick(&control, secs(2)));
+        clock.sync_hands(1, Stone::Black);
+        assert_eq!(clock.black.main, secs(13));
+        for _ in 0..3 {
+            clock.sync_hands(0, Stone::Black);
+            assert_eq!(clock.black.main, secs(8));
+            clock.sync_hands(1, Stone::Black);
+            assert_eq!(clock.black.main, secs(13));
+        }
+        assert_eq!(clock.white.main, secs(10));
+    }
+
+    #[test]
+    fn undo_does_not_refund_time_spent() {
+        let control = TimeControl::Absolute { main: secs(10) };
+        let mut clock = GameClock::new(control);
+        clock.sync_hands(1, Stone::Black);
+        assert!(!clock.white.tick(&control, secs(4)));
+        clock.sync_hands(0, Stone::Black);
+        assert_eq!(clock.white.main, secs(6));
+    }
+
+    #[test]
+    fn redo_does_not_refill_the_byo_yomi_period() {
+        let mut clock = GameClock::new(BYO_YOMI);
+        assert!(!clock.black.tick(&BYO_YOMI, secs(13)));
+        clock.sync_hands(1, Stone::Black);
+        assert_eq!(clock.black.period_left, secs(5));
+        clock.sync_hands(0, Stone::Black);
+        assert_eq!(clock.black.period_left, secs(2));
+        assert!(!clock.black.tick(&BYO_YOMI, secs(1)));
+        clock.sync_hands(1, Stone::Black);
+        assert_eq!(clock.black.period_left, secs(5));
+        clock.sync_hands(0, Stone::Black);
+        assert_eq!(clock.black.period_left, secs(1));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::{
+    AppState, Controller, GameSettings, PlayerControllers,
+    agent::{AgentRegistry, SelectedAgents},
+    clock::TimeControl,
+    ui_button::{ButtonClicked, create_button, set_button_label},
+};
+use bevy::prelude::*;
+use configure::Difficulty;
+use std::time::Duration;
+
+const BOARD_SIZES: [u8; 3] = [9, 13, 19];
+const HANDICAPS: [u8; 9] = [0, 2, 3, 4, 5, 6, 7, 8, 9];
+const KOMIS: [f32; 5] = [0.0, 0.5, 5.5, 6.5, 7.5];
+
+const TIME_CONTROLS: [TimeControl; 4] = [
+    TimeControl::Unlimited,
+    TimeControl::Absolute {
+        main: Duration::from_secs(10 * 60),
+    },
+    TimeControl::ByoYomi {
+        main: Duration::from_secs(5 * 60),
+        period: Duration::from_secs(30),
+        periods: 3,
+    },
+    TimeControl::Fischer {
+        main: Duration::from_secs(5 * 60),
+        increment: Duration::from_secs(10),
+    },
+];
+
+const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+const GAME_MODES: [PlayerControllers; 3] = [
+    PlayerControllers {
+        black: Controller::Human,
+        white: Controller::Human,
+    },
+    PlayerControllers {
+        black: Controller::Human,
+        white: Controller::Ai,
+    },
+    PlayerControllers {
+        black: Controller::Ai,
+        white: Controller::Ai,
+    },
+];
+
+/// The option after `current` in `options`, wrapping around to the first.
+fn next_option<T: Copy + PartialEq>(options: &[T], current: T) -> T {
+    let next = options
+        .iter()
+        .position(|option| *option == current)
+        .map_or(0, |idx| (idx + 1) % options.len());
+    options[next]
+}
+
+fn mode_label(controllers: &PlayerControllers) -> String {
+    let name = |controller: Controller| match controller {
+        Controller::Human => "Human",
+        Controller::Ai => "AI",
+    };
+    format!(
+        "Mode: {} vs {}",
+        name(controllers.black),
+        name(controllers.white)
+    )
+}
+
+fn board_size_label(settings: &GameSettings) -> String {
+    format!("Board: {0}x{0}", settings.board_size)
+}
+
+fn handicap_label(settings: &GameSettings) -> String {
+    match settings.handicap {
+        0 => "Handicap: None".into(),
+        stones => format!("Handicap: {stones} stones"),
+    }
+}
+
+fn komi_label(settings: &GameSettings) -> String {
+    format!("Komi: {}", settings.komi)
+}
+
+fn time_control_label(settings: &GameSettings) -> String {
+    format!("Time: {}", settings.time_control)
+}
+
+fn difficulty_label(settings: &GameSettings) -> String {
+    format!("Difficulty: {:?}", settings.difficulty)
+}
+
+/// Marks the button choosing the agent that plays `0` when it is AI-controlled.
+#[derive(Component)]
+struct AgentOption(igo_core::Stone);
+
+fn agent_label(stone: igo_core::Stone, selected: &SelectedAgents) -> String {
+    match stone {
+        igo_core::Stone::Black => format!("Black AI: {}", selected.black),
+        igo_core::Stone::White => format!("White AI: {}", selected.white),
+    }
+}
+
+pub fn setup_game_setup_ui(
+    mut commands: Commands,
+    controllers: Res<PlayerControllers>,
+    selected: Res<SelectedAgents>,
+    settings: Res<GameSettings>,
+) {
+    use bevy::color::palettes::tailwind::*;
+    log::trace!("Setting up game setup UI...");
+    commands
+        .spawn((
+            DespawnOnExit(AppState::GameSetup),
+            Node {
+                width: percent(100),
+                height: percent(100),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                row_gap: px(10.0),
+                ..Default::default()
+            },
+            BackgroundColor(Color::Srgba(GRAY_800)),
+        ))
+        .with_children(|p| {
+            p.spawn((create_button(&mode_label(&controllers)),))
+                .observe(on_cycle_mode);
+            p.spawn((create_button(&board_size_label(&settings)),))
+                .observe(on_cycle_board_size);
+            p.spawn((create_button(&handicap_label(&settings)),))
+                .observe(on_cycle_handicap);
+            p.spawn((create_button(&komi_label(&settings)),))
+                .observe(on_cycle_komi);
+            p.spawn((create_button(&time_control_label(&settings)),))
+                .observe(on_cycle_time_control);
+            p.spawn((create_button(&difficulty_label(&settings)),))
+                .observe(on_cycle_difficulty);
+            for stone in [igo_core::Stone::Black, igo_core::Stone::White] {
+                p.spawn((
+                    AgentOption(stone),
+                    create_button(&agent_label(stone, &selected)),
+                ))
+                .observe(on_cycle_agent);
+            }
+            p.spawn((create_button("Start"),)).observe(on_start);
+            p.spawn((create_button("Back"),)).observe(on_back);
+        });
+}
+
+fn on_cycle_mode(
+    event: On<ButtonClicked>,
+    mut controllers: ResMut<PlayerControllers>,
+    children: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    *controllers = next_option(&GAME_MODES, *controllers);
+    log::debug!("Game mode: {:?}", *controllers);
+    set_button_label(
+        event.event_target(),
+        &mode_label(&controllers),
+        &children,
+        &mut texts,
+    );
+}
+
+fn on_cycle_board_size(
+    event: On<ButtonClicked>,
+    mut settings: ResMut<GameSettings>,
+    children: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    settings.board_size = next_option(&BOARD_SIZES, settings.board_size);
+    log::debug!("Board size: {}", settings.board_size);
+    set_button_label(
+        event.event_target(),
+        &board_size_label(&settings),
+        &children,
+        &mut texts,
+    );
+}
+
+fn on_cycle_handicap(
+    event: On<ButtonClicked>,
+    mut settings: ResMut<GameSettings>,
+    children: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    settings.handicap = next_option(&HANDICAPS, settings.handicap);
+    log::debug!("Handicap: {}", settings.handicap);
+    set_button_label(
+        event.event_target(),
+        &handicap_label(&settings),
+        &children,
+        &mut texts,
+    );
+}
+
+fn on_cycle_komi(
+    event: On<ButtonClicked>,
+    mut settings: ResMut<GameSettings>,
+    children: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    settings.komi = next_option(&KOMIS, settings.komi);
+    log::debug!("Komi: {}", settings.komi);
+    set_button_label(
+        event.event_target(),
+        &komi_label(&settings),
+        &children,
+        &mut texts,
+    );
+}
+
+fn on_cycle_time_control(
+    event: On<ButtonClicked>,
+    mut settings: ResMut<GameSettings>,
+    children: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    settings.time_control = next_option(&TIME_CONTROLS, settings.time_control);
+    log::debug!("Time control: {}", settings.time_control);
+    set_button_label(
+        event.event_target(),
+        &time_control_label(&settings),
+        &children,
+        &mut texts,
+    );
+}
+
+/// Picks the agents for the next difficulty and remembers the choice in the config.
+fn on_cycle_difficulty(
+    event: On<ButtonClicked>,
+    mut settings: ResMut<GameSettings>,
+    mut selected: ResMut<SelectedAgents>,
+    agent_buttons: Query<(Entity, &AgentOption)>,
+    children: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    settings.difficulty = next_option(&DIFFICULTIES, settings.difficulty);
+    *selected = SelectedAgents::for_difficulty(settings.difficulty);
+    log::debug!("Difficulty: {:?} ({:?})", settings.difficulty, *selected);
+    let mut config = configure::Config::load(configure::CONFIG_PATH);
+    config.difficulty = settings.difficulty;
+    config.save(configure::CONFIG_PATH);
+    set_button_label(
+        event.event_target(),
+        &difficulty_label(&settings),
+        &children,
+        &mut texts,
+    );
+    for (entity, AgentOption(stone)) in &agent_buttons {
+        set_button_label(
+            entity,
+            &agent_label(*stone, &selected),
+            &children,
+            &mut texts,
+        );
+    }
+}
+
+fn on_cycle_agent(
+    event: On<ButtonClicked>,
+    options: Query<&AgentOption>,
+    registry: Res<AgentRegistry>,
+    mut selected: ResMut<SelectedAgents>,
+    children: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    let Ok(AgentOption(stone)) = options.get(event.event_target()) else {
+        return;
+    };
+    let current = match stone {
+        igo_core::Stone::Black => &mut selected.black,
+        igo_core::Stone::White => &mut selected.white,
+    };
+    let names = registry.names().collect::<Vec<_>>();
+    if !names.is_empty() {
+        *current = next_option(&names, *current);
+    }
+    log::debug!("Selected agents: {:?}", *selected);
+    set_button_label(
+        event.event_target(),
+        &agent_label(*stone, &selected),
+        &children,
+        &mut texts,
+    );
+}
+
+fn on_start(_event: On<ButtonClicked>, mut state: ResMut<NextState<AppState>>) {
+    log::trace!("Starting the game...");
+    state.set(AppState::InGame);
+}
+
+fn on_back(_event: On<ButtonClicked>, mut state: ResMut<NextState<AppState>>) {
+    log::trace!("Back to title...");
+    state.set(AppState::Title);
+}
+
+pub fn back_to_title(input: Res<ButtonInput<KeyCode>>, mut state: ResMut<NextState<AppState>>) {
+    if input.just_pressed(KeyCode::Escape) {
+        state.set(AppState::Title)
+    }
+}
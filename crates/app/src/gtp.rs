@@ -0,0 +1,376 @@
+//! Go Text Protocol helpers shared by the GTP client agent and the headless
+//! GTP server that exposes the app's agents to other programs.
+
+use crate::{
+    agent::{AgentRegistry, Decision, GoAgent, MctsAgent},
+    rules,
+};
+use igo_core::{Game, GameHand, Stone};
+use std::{
+    io::{self, BufRead, Write},
+    sync::atomic::AtomicBool,
+};
+
+/// Largest board a GTP vertex can name with our column letters.
+const MAX_BOARD_SIZE: u8 = 25;
+
+/// GTP color argument for `stone`.
+pub fn color_name(stone: Stone) -> &'static str {
+    match stone {
+        Stone::Black => "B",
+        Stone::White => "W",
+    }
+}
+
+/// Parses a GTP color such as `b`, `W` or `black`.
+pub fn parse_color(text: &str) -> Option<Stone> {
+    match text.to_ascii_lowercase().as_str() {
+        "b" | "black" => Some(Stone::Black),
+        "w" | "white" => Some(Stone::White),
+        _ => None,
+    }
+}
+
+/// GTP vertex such as `D4` for intersection `(x, y)` on a board of `size` lines.
+pub fn format_vertex(x: u8, y: u8, size: u8) -> String {
+    format!("{}{}", rules::column_name(x), rules::row_name(y, size))
+}
+
+/// Intersection named by a GTP vertex; `None` for `pass` or anything off the board.
+pub fn parse_vertex(text: &str, size: u8) -> Option<(u8, u8)> {
+    let mut chars = text.chars();
+    let column = chars.next()?.to_ascii_uppercase();
+    let x = (0..size).find(|&x| rules::column_name(x) == column)?;
+    let row = chars.as_str().parse::<u8>().ok()?;
+    (1..=size).contains(&row).then(|| (x, size - row))
+}
+
+const COMMANDS: &[&str] = &[
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "play",
+    "genmove",
+    "undo",
+    "final_score",
+    "showboard",
+];
+
+/// Answers GTP commands with an `igo_core::Game` and one of the app's agents.
+pub struct GtpServer {
+    game: Game,
+    komi: f32,
+    /// Stones Black and White have captured so far.
+    captured: (u32, u32),
+    /// Positions and captures before each hand, for `undo`.
+    history: Vec<(Game, (u32, u32))>,
+    agent: Box<dyn GoAgent>,
+}
+
+impl GtpServer {
+    pub fn new(mut agent: Box<dyn GoAgent>) -> Self {
+        agent.set_komi(rules::DEFAULT_KOMI);
+        Self {
+            game: Game::default(),
+            komi: rules::DEFAULT_KOMI,
+            captured: (0, 0),
+            history: Vec::new(),
+            agent,
+        }
+    }
+
+    /// Reads commands from `input` until `quit` or end of input.
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            // Comments start with `#`; control characters other than tabs are dropped.
+            let line = line?;
+            let line = line.split('#').next().unwrap_or_default();
+            let line = line
+                .chars()
+                .filter(|c| !c.is_control() || *c == '\t')
+                .collect::<String>();
+            let mut words = line.split_whitespace().peekable();
+            let id = words
+                .next_if(|word| word.chars().all(|c| c.is_ascii_digit()))
+                .unwrap_or_default();
+            let Some(command) = words.next() else {
+                continue;
+            };
+            let args = words.collect::<Vec<_>>();
+            let (ok, text) = match self.execute(command, &args) {
+                Ok(text) => ("=", text),
+                Err(text) => ("?", text),
+            };
+            let separator = if text.contains('\n') { "\n" } else { " " };
+            write!(output, "{ok}{id}{separator}{text}\n\n")?;
+            output.flush()?;
+            if command == "quit" {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, command: &str, args: &[&str]) -> Result<String, String> {
+        log::debug!("GTP command: {command} {args:?}");
+        match command {
+            "protocol_version" => Ok("2".into()),
+            "name" => Ok(env!("CARGO_PKG_NAME").into()),
+            "version" => Ok(env!("CARGO_PKG_VERSION").into()),
+            "known_command" => Ok(args
+                .first()
+                .is_some_and(|name| COMMANDS.contains(name))
+                .to_string()),
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "quit" => Ok(String::new()),
+            "boardsize" => {
+                let size = args
+                    .first()
+                    .and_then(|size| size.parse::<u8>().ok())
+                    .filter(|size| (2..=MAX_BOARD_SIZE).contains(size))
+                    .ok_or("unacceptable size")?;
+                self.clear(size);
+                Ok(String::new())
+            }
+            "clear_board" => {
+                self.clear(self.game.size());
+                Ok(String::new())
+            }
+            "komi" => {
+                let komi = args
+                    .first()
+                    .and_then(|komi| komi.parse::<f32>().ok())
+                    .ok_or("syntax error")?;
+                self.komi = komi;
+                self.agent.set_komi(komi);
+                Ok(String::new())
+            }
+            "play" => {
+                let (Some(color), Some(vertex)) = (args.first(), args.get(1)) else {
+                    return Err("syntax error".into());
+                };
+                let stone = parse_color(color).ok_or("syntax error")?;
+                let hand = if vertex.eq_ignore_ascii_case("pass") {
+                    GameHand::pass(stone)
+                } else {
+                    let (x, y) = parse_vertex(vertex, self.game.size()).ok_or("syntax error")?;
+                    rules::find_hand(&self.game, stone, x, y).ok_or("illegal move")?
+                };
+                self.apply(stone, hand);
+                Ok(String::new())
+            }
+            "genmove" => {
+                let stone = args
+                    .first()
+                    .and_then(|color| parse_color(color))
+                    .ok_or("syntax error")?;
+                let decision = self
+                    .agent
+                    .select_hand(&self.game, stone, &AtomicBool::new(false));
+                let Decision::Play(hand) = decision else {
+                    return Ok("resign".into());
+                };
+                let vertex = match rules::hand_position(&self.game, hand) {
+                    Some((x, y)) => format_vertex(x, y, self.game.size()),
+                    None => "pass".into(),
+                };
+                self.apply(stone, hand);
+                Ok(vertex)
+            }
+            "undo" => {
+                (self.game, self.captured) = self.history.pop().ok_or("cannot undo")?;
+                Ok(String::new())
+            }
+            "final_score" => {
+                // Scored like the app, with the stones an agent would mark dead.
+                let dead = rules::propose_dead_stones(&self.game);
+                let (black, white) =
+                    rules::territory_score(&self.game, &dead, self.captured, self.komi);
+                let margin = black.total() - white.total();
+                Ok(if margin > 0. {
+                    format!("B+{margin}")
+                } else if margin < 0. {
+                    format!("W+{}", -margin)
+                } else {
+                    "0".into()
+                })
+            }
+            "showboard" => Ok(self.board_text()),
+            _ => Err("unknown command".into()),
+        }
+    }
+
+    fn clear(&mut self, size: u8) {
+        self.game = Game::new(size);
+        self.captured = (0, 0);
+        self.history.clear();
+    }
+
+    fn apply(&mut self, stone: Stone, hand: GameHand) {
+        self.history.push((self.game.clone(), self.captured));
+        let before = rules::count_stones(&self.game, stone.opposite());
+        self.game.put_hand(hand);
+        let captured = before.saturating_sub(rules::count_stones(&self.game, stone.opposite()));
+        match stone {
+            Stone::Black => self.captured.0 += captured as u32,
+            Stone::White => self.captured.1 += captured as u32,
+        }
+    }
+
+    fn board_text(&self) -> String {
+        let size = self.game.size();
+        let columns = (0..size)
+            .map(|x| rules::column_name(x).to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut lines = vec![format!("   {columns}")];
+        for y in 0..size {
+            let row = (0..size)
+                .map(|x| match self.game.board().get_stone(x, y) {
+                    Some(Stone::Black) => "X",
+                    Some(Stone::White) => "O",
+                    None => ".",
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            lines.push(format!("{:>2} {row}", rules::row_name(y, size)));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Entry point of the `igo_gtp` binary. Accepts `--agent NAME` to pick one of
+/// the registered agents (MCTS by default) and `--seed N` for its RNG.
+pub fn run() {
+    let mut agent_name = MctsAgent::NAME.to_string();
+    let mut seed = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--agent", Some(name)) => agent_name = name,
+            ("--seed", Some(value)) => seed = value.parse().ok(),
+            _ => eprintln!("Unknown or incomplete argument: {arg}"),
+        }
+    }
+    let registry = AgentRegistry::default();
+    let Some(agent) = registry.create(&agent_name, seed.unwrap_or_else(rand::random)) else {
+        let names = registry.names().collect::<Vec<_>>().join(", ");
+        eprintln!("Unknown agent {agent_name}; choose one of: {names}");
+        std::process::exit(2);
+    };
+    let mut server = GtpServer::new(agent);
+    if let Err(err) = server.serve(io::stdin().lock(), io::stdout().lock()) {
+        eprintln!("GTP server stopped: {err}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::RandomAgent;
+
+    /// Output of a server fed `input`.
+    fn session(input: &str) -> String {
+        let mut server = GtpServer::new(Box::new(RandomAgent::new(0)));
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn responses_echo_the_command_id() {
+        assert_eq!(
+            session("1 protocol_version\nversion\n"),
+            format!("=1 2\n\n= {}\n\n", env!("CARGO_PKG_VERSION"))
+        );
+    }
+
+    #[test]
+    fn failures_are_framed_with_a_question_mark() {
+        assert_eq!(
+            session("7 frobnicate\nplay x A1\n"),
+            "?7 unknown command\n\n? syntax error\n\n"
+        );
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_skipped() {
+        assert_eq!(session("# setup\n\n2 komi 5.5 # Japanese\n"), "=2 \n\n");
+    }
+
+    #[test]
+    fn known_command_answers_true_or_false() {
+        assert_eq!(
+            session("known_command genmove\nknown_command frobnicate\nknown_command\n"),
+            "= true\n\n= false\n\n= false\n\n"
+        );
+    }
+
+    #[test]
+    fn multi_line_responses_start_on_a_new_line() {
+        let output = session("boardsize 3\nplay b B2\nshowboard\n");
+        assert_eq!(
+            output.split("\n\n").nth(2).unwrap(),
+            "=\n   A B C\n 3 . . .\n 2 . X .\n 1 . . ."
+        );
+    }
+
+    #[test]
+    fn play_and_undo() {
+        assert_eq!(
+            session("boardsize 5\nplay b C3\nplay w C3\nundo\nplay w C3\nundo\nundo\n"),
+            "= \n\n= \n\n? illegal move\n\n= \n\n= \n\n= \n\n? cannot undo\n\n"
+        );
+    }
+
+    #[test]
+    fn boardsize_rejects_unsupported_sizes() {
+        assert_eq!(
+            session("boardsize 30\nboardsize 1\nboardsize nine\nboardsize 25\n"),
+            "? unacceptable size\n\n? unacceptable size\n\n? unacceptable size\n\n= \n\n"
+        );
+    }
+
+    #[test]
+    fn vertices_skip_column_i() {
+        assert_eq!(parse_vertex("J9", 19), Some((8, 10)));
+        assert_eq!(parse_vertex("j9", 19), Some((8, 10)));
+        assert_eq!(parse_vertex("A19", 19), Some((0, 0)));
+        assert_eq!(parse_vertex("I9", 19), None);
+        assert_eq!(parse_vertex("T20", 19), None);
+        assert_eq!(parse_vertex("A0", 19), None);
+        assert_eq!(parse_vertex("pass", 19), None);
+        assert_eq!(format_vertex(8, 10, 19), "J9");
+    }
+
+    #[test]
+    fn final_score_counts_territory_prisoners_and_dead_stones() {
+        // Black walls off columns A-B, White column E; White's A5 is dead.
+        let mut input = String::from("boardsize 5\nkomi 0\n");
+        for row in 1..=5 {
+            input += &format!("play b C{row}\nplay w D{row}\n");
+        }
+        input += "play w A5\nfinal_score\nkomi 6.5\nfinal_score\n";
+        let output = session(&input);
+        let responses = output.split("\n\n").collect::<Vec<_>>();
+        assert_eq!((responses[13], responses[15]), ("= B+6", "= W+0.5"));
+    }
+
+    #[test]
+    fn undo_restores_captures() {
+        let output = session(
+            "boardsize 5\nkomi 0\nplay w A5\nplay b B5\nplay b A4\nfinal_score\nundo\nundo\nfinal_score\n",
+        );
+        let responses = output.split("\n\n").collect::<Vec<_>>();
+        // 23 points of territory plus the captured A5.
+        assert_eq!(responses[5], "= B+24");
+        assert_eq!(responses[8], "= W+24");
+    }
+}
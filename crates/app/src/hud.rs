@@ -0,0 +1,38 @@
+use crate::{AppState, GoGameResource};
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct HudText;
+
+pub fn setup_hud(mut commands: Commands) {
+    log::trace!("Setting up HUD");
+    commands.spawn((
+        DespawnOnExit(AppState::InGame),
+        HudText,
+        Node {
+            position_type: PositionType::Absolute,
+            left: px(40.0),
+            top: percent(40),
+            ..Default::default()
+        },
+        Text::default(),
+        TextColor(Color::WHITE),
+        TextFont {
+            font_size: 24.0,
+            ..Default::default()
+        },
+    ));
+}
+
+/// Rewrites the side panel; scheduled only when [`GoGameResource`] has changed.
+pub fn update_hud(game: Res<GoGameResource>, mut text: Single<&mut Text, With<HudText>>) {
+    log::trace!("Updating HUD");
+    text.0 = format!(
+        "{:?} to play\nMove {}\nKomi {}\nCaptured by Black: {}\nCaptured by White: {}",
+        game.turn,
+        game.moves.len() + 1,
+        game.komi,
+        game.prisoners.black,
+        game.prisoners.white,
+    );
+}
@@ -0,0 +1,123 @@
+use crate::{AppState, GoGameResource, in_game::BoardLayout, rules};
+use bevy::prelude::*;
+
+const FLASH_SECONDS: f32 = 0.4;
+const MESSAGE_SECONDS: f32 = 2.0;
+const FLASH_ALPHA: f32 = 0.7;
+
+/// A human clicked an intersection without an allowed hand.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct IllegalMoveAttempt {
+    pub x: u8,
+    pub y: u8,
+    pub reason: rules::IllegalMove,
+}
+
+/// Red square on the point that may not be retaken while a ko is active.
+#[derive(Component)]
+pub struct KoMarker;
+
+/// Circle fading out over the rejected intersection.
+#[derive(Component)]
+pub struct IllegalMoveFlash(Timer);
+
+/// Text explaining the last rejected hand, cleared when the timer finishes.
+#[derive(Component)]
+pub struct IllegalMoveMessage(Timer);
+
+pub fn setup_illegal_move_message(mut commands: Commands) {
+    commands.spawn((
+        DespawnOnExit(AppState::InGame),
+        IllegalMoveMessage(Timer::from_seconds(MESSAGE_SECONDS, TimerMode::Once)),
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            bottom: px(10.0),
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+        Text::default(),
+        TextColor(Color::Srgba(bevy::color::palettes::tailwind::RED_400)),
+        TextLayout {
+            justify: Justify::Center,
+            ..Default::default()
+        },
+        TextFont {
+            font_size: 24.0,
+            ..Default::default()
+        },
+    ));
+}
+
+pub fn show_illegal_move(
+    mut commands: Commands,
+    mut attempts: MessageReader<IllegalMoveAttempt>,
+    layout: Res<BoardLayout>,
+    mut message: Single<(&mut Text, &mut IllegalMoveMessage)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let (text, timer) = &mut *message;
+    for attempt in attempts.read() {
+        text.0 = format!(
+            "Illegal move at {}{}: {}",
+            rules::column_name(attempt.x),
+            rules::row_name(attempt.y, layout.line_count),
+            attempt.reason
+        );
+        timer.0.reset();
+        commands.spawn((
+            DespawnOnExit(AppState::InGame),
+            IllegalMoveFlash(Timer::from_seconds(FLASH_SECONDS, TimerMode::Once)),
+            Mesh2d(meshes.add(Circle::new(layout.stone_radius))),
+            MeshMaterial2d(materials.add(ColorMaterial::from(Color::srgba(
+                1.0,
+                0.0,
+                0.0,
+                FLASH_ALPHA,
+            )))),
+            Transform::from_translation(layout.position(attempt.x, attempt.y).extend(2.)),
+        ));
+    }
+}
+
+pub fn fade_illegal_move_feedback(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flashes: Query<(
+        Entity,
+        &mut IllegalMoveFlash,
+        &MeshMaterial2d<ColorMaterial>,
+    )>,
+    mut message: Single<(&mut Text, &mut IllegalMoveMessage)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, mut flash, material) in &mut flashes {
+        if flash.0.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+        } else if let Some(material) = materials.get_mut(&material.0) {
+            material
+                .color
+                .set_alpha(FLASH_ALPHA * flash.0.fraction_remaining());
+        }
+    }
+    let (text, timer) = &mut *message;
+    if !text.0.is_empty() && timer.0.tick(time.delta()).just_finished() {
+        text.0.clear();
+    }
+}
+
+pub fn update_ko_marker(
+    game: Res<GoGameResource>,
+    layout: Res<BoardLayout>,
+    mut marker: Single<(&mut Transform, &mut Visibility), With<KoMarker>>,
+) {
+    let (transform, visibility) = &mut *marker;
+    match game.ko {
+        Some((x, y)) => {
+            transform.translation = layout.position(x, y).extend(1.);
+            **visibility = Visibility::Visible;
+        }
+        None => **visibility = Visibility::Hidden,
+    }
+}
@@ -1,17 +1,56 @@
 use crate::{
-    AppState, GameState, GoGameResource,
+    AppState, Controller, GameEnd, GameSeed, GameSettings, GameState, GoGameResource,
+    PlayerControllers,
+    agent::{ActiveAgents, Decision, setup_agents},
+    clock::setup_clock,
+    illegal_move::KoMarker,
+    inspect::{AtariOutline, GroupHighlight},
+    move_marks::{LastMoveMarker, MoveNumber},
     pause::on_back_to_title,
+    playback::SingleStep,
+    rules,
     ui_button::{ButtonClicked, create_button},
 };
 use bevy::prelude::*;
 
-pub const STONE_RADIUS: f32 = 22.5;
+/// Stone radius relative to the line spacing; below one half so stones never overlap.
+const STONE_RADIUS_RATIO: f32 = 0.46;
 pub const LINE_COLOR: Color = Color::Srgba(bevy::color::palettes::tailwind::GRAY_800);
 
+/// Geometry of the board currently on screen, relative to the board entity.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct BoardLayout {
+    pub line_count: u8,
+    /// Distance between the first and the last line.
+    pub extent: f32,
+    pub spacing: f32,
+    pub stone_radius: f32,
+}
+
+impl BoardLayout {
+    fn new(line_count: u8, extent: f32) -> Self {
+        let spacing = extent / (line_count - 1) as f32;
+        Self {
+            line_count,
+            extent,
+            spacing,
+            stone_radius: spacing * STONE_RADIUS_RATIO,
+        }
+    }
+
+    /// Center of intersection `(x, y)`; `y` counts down from the top line.
+    pub fn position(&self, x: u8, y: u8) -> Vec2 {
+        Vec2::new(
+            -self.extent / 2. + x as f32 * self.spacing,
+            self.extent / 2. - y as f32 * self.spacing,
+        )
+    }
+}
+
 #[derive(Component)]
 pub struct StonePos {
-    x: u8,
-    y: u8,
+    pub x: u8,
+    pub y: u8,
 }
 
 fn create_2d_mesh(
@@ -34,11 +73,14 @@ pub fn setup_in_game_ui(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut game: ResMut<GoGameResource>,
+    settings: Res<GameSettings>,
 ) {
     use bevy::color::palettes::tailwind::*;
     log::trace!("Setting up in-game UI");
     let window_resolution = window.size();
-    game.game.reset();
+    *game = GoGameResource::new(&settings);
+    let layout = BoardLayout::new(game.game.size(), window_resolution.y * 0.9);
+    commands.insert_resource(layout);
     commands
         .spawn((
             DespawnOnExit(AppState::InGame),
@@ -48,94 +90,287 @@ pub fn setup_in_game_ui(
             MeshMaterial2d(materials.add(ColorMaterial::from(Color::Srgba(AMBER_500)))),
         ))
         .with_children(|p| {
-            let board_size = window_resolution.y * 0.9;
+            let board_size = layout.extent;
 
             let line_color = materials.add(ColorMaterial::from(LINE_COLOR));
             let stone_color = materials.add(ColorMaterial::from(Color::BLACK));
             let horizontal_line = meshes.add(Rectangle::new(board_size, 3.));
             let vertical_line = meshes.add(Rectangle::new(3., board_size));
-            let star_circle = meshes.add(Circle::new(STONE_RADIUS / 4.));
-            let stone_circle = meshes.add(Circle::new(STONE_RADIUS));
+            let star_circle = meshes.add(Circle::new(layout.stone_radius / 4.));
+            let stone_circle = meshes.add(Circle::new(layout.stone_radius));
+            let marker_ring = meshes.add(Annulus::new(
+                layout.stone_radius * 0.55,
+                layout.stone_radius * 0.7,
+            ));
+            let marker_color = materials.add(ColorMaterial::from(Color::Srgba(RED_500)));
+            let outline_ring = meshes.add(Annulus::new(
+                layout.stone_radius * 0.85,
+                layout.stone_radius * 1.05,
+            ));
+            let highlight_color = materials.add(ColorMaterial::from(Color::Srgba(SKY_400)));
+            let atari_color = materials.add(ColorMaterial::from(Color::Srgba(ORANGE_500)));
+            let number_font = TextFont {
+                font_size: layout.stone_radius * 0.9,
+                ..Default::default()
+            };
 
-            let line_count = game.game.size();
+            let line_count = layout.line_count;
             for i in 0..line_count {
-                let pos = -board_size / 2. + (i as f32) * (board_size / (line_count - 1) as f32);
+                let pos = layout.position(i, i);
                 p.spawn(create_2d_mesh(
                     horizontal_line.clone(),
                     line_color.clone(),
                     Visibility::Visible,
-                    Vec3::new(0., pos, 0.),
+                    Vec3::new(0., pos.y, 0.),
                 ));
                 p.spawn(create_2d_mesh(
                     vertical_line.clone(),
                     line_color.clone(),
                     Visibility::Visible,
-                    Vec3::new(pos, 0., 0.),
+                    Vec3::new(pos.x, 0., 0.),
+                ));
+            }
+            for &(x, y) in rules::star_points(line_count) {
+                p.spawn(create_2d_mesh(
+                    star_circle.clone(),
+                    stone_color.clone(),
+                    Visibility::Visible,
+                    layout.position(x, y).extend(0.),
                 ));
             }
-            let is_star = |x: u8, y: u8| {
-                x > 0
-                    && y > 0
-                    && x < line_count as u8
-                    && y < line_count as u8
-                    && x % 3 == 0
-                    && x % 2 != 0
-                    && y % 3 == 0
-                    && y % 2 != 0
+            // Coordinate labels sit in the margin between the outer lines and the board edge.
+            let margin = (window_resolution.y - board_size) / 2.;
+            let label_offset = board_size / 2. + margin / 2.;
+            let label_font = TextFont {
+                font_size: (margin * 0.6).min(layout.spacing * 0.6),
+                ..Default::default()
             };
+            for i in 0..line_count {
+                let pos = layout.position(i, i);
+                let column = rules::column_name(i).to_string();
+                let row = rules::row_name(i, line_count).to_string();
+                for (label, offset) in [
+                    (&column, Vec2::new(pos.x, label_offset)),
+                    (&column, Vec2::new(pos.x, -label_offset)),
+                    (&row, Vec2::new(-label_offset, pos.y)),
+                    (&row, Vec2::new(label_offset, pos.y)),
+                ] {
+                    p.spawn((
+                        Text2d::new(label.as_str()),
+                        TextColor(LINE_COLOR),
+                        label_font.clone(),
+                        Transform::from_translation(offset.extend(0.)),
+                    ));
+                }
+            }
             for x in 0..line_count {
                 for y in 0..line_count {
-                    if is_star(x, y) {
-                        let pos_x =
-                            -board_size / 2. + (x as f32) * (board_size / (line_count - 1) as f32);
-                        let pos_y =
-                            board_size / 2. - (y as f32) * (board_size / (line_count - 1) as f32);
-                        p.spawn(create_2d_mesh(
-                            star_circle.clone(),
-                            stone_color.clone(),
-                            Visibility::Visible,
-                            Vec3::new(pos_x, pos_y, 0.),
-                        ));
-                    }
-                    let pos_x =
-                        -board_size / 2. + (x as f32) * (board_size / (line_count - 1) as f32);
-                    let pos_y =
-                        board_size / 2. - (y as f32) * (board_size / (line_count - 1) as f32);
+                    let pos = layout.position(x, y);
                     p.spawn(create_2d_mesh(
                         stone_circle.clone(),
                         stone_color.clone(),
                         Visibility::Hidden,
-                        Vec3::new(pos_x, pos_y, 0.),
+                        pos.extend(0.),
                     ))
-                    .insert(StonePos { x, y });
+                    .insert(StonePos { x, y })
+                    .with_children(|s| {
+                        s.spawn((
+                            MoveNumber,
+                            Text2d::default(),
+                            TextColor::WHITE,
+                            number_font.clone(),
+                            Visibility::Hidden,
+                            Transform::from_xyz(0., 0., 1.),
+                        ));
+                        s.spawn(create_2d_mesh(
+                            outline_ring.clone(),
+                            atari_color.clone(),
+                            Visibility::Hidden,
+                            Vec3::new(0., 0., 1.),
+                        ))
+                        .insert(AtariOutline);
+                        s.spawn(create_2d_mesh(
+                            outline_ring.clone(),
+                            highlight_color.clone(),
+                            Visibility::Hidden,
+                            Vec3::new(0., 0., 1.5),
+                        ))
+                        .insert(GroupHighlight);
+                    });
                 }
             }
+            p.spawn(create_2d_mesh(
+                marker_ring,
+                marker_color.clone(),
+                Visibility::Hidden,
+                Vec3::new(0., 0., 1.),
+            ))
+            .insert(LastMoveMarker);
+            p.spawn(create_2d_mesh(
+                meshes.add(Rectangle::from_length(layout.stone_radius * 0.8)),
+                marker_color.clone(),
+                Visibility::Hidden,
+                Vec3::new(0., 0., 1.),
+            ))
+            .insert(KoMarker);
         });
 }
 
-pub fn tick_game(
+pub fn setup_in_game_controls(mut commands: Commands) {
+    log::trace!("Setting up in-game controls");
+    commands
+        .spawn((
+            DespawnOnExit(AppState::InGame),
+            Node {
+                position_type: PositionType::Absolute,
+                right: px(40.0),
+                top: percent(40),
+                flex_direction: FlexDirection::Column,
+                row_gap: px(10.0),
+                ..Default::default()
+            },
+        ))
+        .with_children(|p| {
+            p.spawn((create_button("Pass"),)).observe(on_pass);
+            p.spawn((create_button("Resign"),)).observe(on_resign);
+            p.spawn((create_button("Undo"),)).observe(on_undo);
+            p.spawn((create_button("Redo"),)).observe(on_redo);
+        });
+}
+
+fn on_pass(
+    _event: On<ButtonClicked>,
+    current_state: Res<State<GameState>>,
+    controllers: Res<PlayerControllers>,
     mut game: ResMut<GoGameResource>,
-    mut turn: Local<igo_core::Stone>,
     mut state: ResMut<NextState<GameState>>,
 ) {
-    log::trace!("Ticking game, turn: {:?}", *turn);
-    let hands = game.game.get_allowed_hands(*turn);
-    let hand = if hands.is_empty() {
-        igo_core::GameHand::pass(*turn)
+    if *current_state.get() != GameState::Running || controllers.get(game.turn) != Controller::Human
+    {
+        return;
+    }
+    let hand = igo_core::GameHand::pass(game.turn);
+    if !game.play(hand) {
+        state.set(GameState::Scoring);
+    }
+}
+
+fn on_resign(
+    _event: On<ButtonClicked>,
+    current_state: Res<State<GameState>>,
+    controllers: Res<PlayerControllers>,
+    mut game: ResMut<GoGameResource>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    if *current_state.get() != GameState::Running {
+        return;
+    }
+    // The human resigns; with humans on both sides it is the one to move.
+    let turn = game.turn;
+    let stone = if controllers.get(turn) == Controller::Human
+        || controllers.get(turn.opposite()) != Controller::Human
+    {
+        turn
     } else {
-        let idx = rand::random::<u64>() as usize % hands.len();
-        hands[idx]
+        turn.opposite()
+    };
+    game.resign(stone);
+    state.set(GameState::GameOver);
+}
+
+/// Walks the move history with `step` until a human is on turn again, so
+/// that against the AI an undo also takes back the AI's reply.
+fn step_history(
+    game: &mut GoGameResource,
+    controllers: &PlayerControllers,
+    agents: &mut ActiveAgents,
+    step: fn(&mut GoGameResource) -> bool,
+) {
+    agents.cancel_search();
+    let has_human =
+        controllers.black == Controller::Human || controllers.white == Controller::Human;
+    while step(game) && has_human && controllers.get(game.turn) != Controller::Human {}
+}
+
+fn on_undo(
+    _event: On<ButtonClicked>,
+    current_state: Res<State<GameState>>,
+    controllers: Res<PlayerControllers>,
+    mut agents: ResMut<ActiveAgents>,
+    mut game: ResMut<GoGameResource>,
+) {
+    if *current_state.get() == GameState::Running {
+        step_history(&mut game, &controllers, &mut agents, GoGameResource::undo);
+    }
+}
+
+fn on_redo(
+    _event: On<ButtonClicked>,
+    current_state: Res<State<GameState>>,
+    controllers: Res<PlayerControllers>,
+    mut agents: ResMut<ActiveAgents>,
+    mut game: ResMut<GoGameResource>,
+) {
+    if *current_state.get() == GameState::Running {
+        step_history(&mut game, &controllers, &mut agents, GoGameResource::redo);
+    }
+}
+
+/// Ctrl+Z undoes and Ctrl+Y redoes.
+pub fn undo_redo_keys(
+    input: Res<ButtonInput<KeyCode>>,
+    controllers: Res<PlayerControllers>,
+    mut agents: ResMut<ActiveAgents>,
+    mut game: ResMut<GoGameResource>,
+) {
+    if !input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    if input.just_pressed(KeyCode::KeyZ) {
+        step_history(&mut game, &controllers, &mut agents, GoGameResource::undo);
+    } else if input.just_pressed(KeyCode::KeyY) {
+        step_history(&mut game, &controllers, &mut agents, GoGameResource::redo);
+    }
+}
+
+pub fn tick_game(
+    mut game: ResMut<GoGameResource>,
+    mut agents: ResMut<ActiveAgents>,
+    mut step: ResMut<SingleStep>,
+    mut state: ResMut<NextState<GameState>>,
+) {
+    let turn = game.turn;
+    log::trace!("Ticking game, turn: {:?}", turn);
+    if !agents.controls(turn) {
+        // Human hands come from `board_input`; there is nothing to step.
+        step.0 = false;
+        return;
+    }
+    let Some(decision) = agents.poll_hand(&game.game, turn) else {
+        return;
+    };
+    step.0 = false;
+    let hand = match decision {
+        Decision::Play(hand) => hand,
+        Decision::Resign => {
+            game.resign(turn);
+            state.set(GameState::GameOver);
+            return;
+        }
     };
-    log::debug!("Hand: {:?}", hand);
-    *turn = (*turn).opposite();
-    if !game.game.put_hand(hand) {
-        state.set(GameState::GameOver);
+    if !game.play(hand) {
+        state.set(GameState::Scoring);
+        return;
     }
+    // Searching from this tick on lets the next tick apply the reply, so
+    // the fixed timestep rate is the rate at which hands are played.
+    agents.start_search(&game.game, game.turn);
 }
 
 pub fn update_in_game(
     game: Res<GoGameResource>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut stone_materials: Local<Option<[Handle<ColorMaterial>; 4]>>,
     mut query: Query<(
         &StonePos,
         &mut MeshMaterial2d<ColorMaterial>,
@@ -144,17 +379,32 @@ pub fn update_in_game(
 ) {
     log::trace!("Updating in-game UI");
     let board = game.game.board();
-    let black_material = materials.add(ColorMaterial::from(Color::BLACK));
-    let white_material = materials.add(ColorMaterial::from(Color::WHITE));
+    let [
+        black_material,
+        white_material,
+        dead_black_material,
+        dead_white_material,
+    ] = stone_materials.get_or_insert_with(|| {
+        [
+            Color::BLACK,
+            Color::WHITE,
+            Color::srgba(0., 0., 0., 0.4),
+            Color::srgba(1., 1., 1., 0.4),
+        ]
+        .map(|color| materials.add(ColorMaterial::from(color)))
+    });
     query
         .par_iter_mut()
         .for_each(|(pos, mut material, mut visibility)| {
             if let Some(stone) = board.get_stone(pos.x, pos.y) {
-                let color = match stone {
-                    igo_core::Stone::Black => black_material.clone(),
-                    igo_core::Stone::White => white_material.clone(),
+                let dead = game.dead_stones.contains(&(pos.x, pos.y));
+                let color = match (stone, dead) {
+                    (igo_core::Stone::Black, false) => black_material.clone(),
+                    (igo_core::Stone::White, false) => white_material.clone(),
+                    (igo_core::Stone::Black, true) => dead_black_material.clone(),
+                    (igo_core::Stone::White, true) => dead_white_material.clone(),
                 };
-                *material = MeshMaterial2d(color);
+                material.set_if_neq(MeshMaterial2d(color));
                 *visibility = Visibility::Visible;
             } else {
                 *visibility = Visibility::Hidden;
@@ -162,14 +412,59 @@ pub fn update_in_game(
         });
 }
 
-pub fn setup_game_over_ui(mut commands: Commands, game: Res<GoGameResource>) {
-    let game_result = game.game.judge();
-    log::info!("Game Over: {game_result:?}");
+pub fn setup_game_over_ui(
+    mut commands: Commands,
+    game: Res<GoGameResource>,
+    seed: Res<GameSeed>,
+    agents: Res<ActiveAgents>,
+) {
+    // Only a game decided by counting gets the score breakdown.
+    let (result, rows) = match game.end {
+        Some(GameEnd::Resigned(stone)) => {
+            (format!("{:?} wins by resignation", stone.opposite()), None)
+        }
+        Some(GameEnd::TimedOut(stone)) => (format!("{:?} wins on time", stone.opposite()), None),
+        None => {
+            let (black, white) = game.score();
+            let margin = black.total() - white.total();
+            let result = if margin > 0. {
+                format!("Black wins by {margin}")
+            } else if margin < 0. {
+                format!("White wins by {}", -margin)
+            } else {
+                "Draw".into()
+            };
+            let rows = [
+                ("", "Black".to_string(), "White".to_string()),
+                (
+                    "Territory",
+                    black.territory.to_string(),
+                    white.territory.to_string(),
+                ),
+                (
+                    "Prisoners",
+                    black.prisoners.to_string(),
+                    white.prisoners.to_string(),
+                ),
+                ("Komi", black.komi.to_string(), white.komi.to_string()),
+                (
+                    "Total",
+                    black.total().to_string(),
+                    white.total().to_string(),
+                ),
+            ];
+            (result, Some(rows))
+        }
+    };
+    log::info!("Game Over: {result}");
+    // A side panel rather than a full-screen overlay keeps the territory visible.
     commands
         .spawn((
             DespawnOnExit(GameState::GameOver),
             Node {
-                width: percent(100),
+                position_type: PositionType::Absolute,
+                right: px(0.0),
+                width: percent(22),
                 height: percent(100),
                 align_items: AlignItems::Center,
                 justify_content: JustifyContent::Center,
@@ -204,7 +499,7 @@ pub fn setup_game_over_ui(mut commands: Commands, game: Res<GoGameResource>) {
                     },
                 ));
                 p.spawn((
-                    Text(format!("Winner: {:?}", game_result)),
+                    Text(result),
                     TextColor(Color::WHITE),
                     TextLayout {
                         justify: Justify::Center,
@@ -215,6 +510,49 @@ pub fn setup_game_over_ui(mut commands: Commands, game: Res<GoGameResource>) {
                         ..Default::default()
                     },
                 ));
+                if let Some(rows) = rows {
+                    p.spawn(Node {
+                        display: Display::Grid,
+                        grid_template_columns: RepeatedGridTrack::flex(3, 1.0),
+                        column_gap: px(20.0),
+                        row_gap: px(5.0),
+                        ..Default::default()
+                    })
+                    .with_children(|p| {
+                        for (label, black, white) in rows {
+                            for cell in [label.to_string(), black, white] {
+                                p.spawn((
+                                    Text(cell),
+                                    TextColor(Color::WHITE),
+                                    TextFont {
+                                        font_size: 20.0,
+                                        ..Default::default()
+                                    },
+                                ));
+                            }
+                        }
+                    });
+                }
+                let seed_text = if agents.replayable() {
+                    format!("Seed: {}", seed.current)
+                } else {
+                    format!(
+                        "Seed: {} (time-limited or external agents;\nnot exactly replayable)",
+                        seed.current
+                    )
+                };
+                p.spawn((
+                    Text(seed_text),
+                    TextColor(Color::WHITE),
+                    TextLayout {
+                        justify: Justify::Center,
+                        ..Default::default()
+                    },
+                    TextFont {
+                        font_size: 20.0,
+                        ..Default::default()
+                    },
+                ));
             });
             p.spawn(Node {
                 width: percent(100),
@@ -235,11 +573,15 @@ pub fn setup_game_over_ui(mut commands: Commands, game: Res<GoGameResource>) {
 
 fn on_reset_game(
     _event: On<ButtonClicked>,
+    mut commands: Commands,
     mut game: ResMut<GoGameResource>,
     mut state: ResMut<NextState<GameState>>,
 ) {
     log::trace!("Resetting game");
     log::info!("Resetting game");
-    game.game.reset();
+    // Fresh agents start the new game from its own seed.
+    commands.run_system_cached(setup_agents);
+    commands.run_system_cached(setup_clock);
+    game.reset();
     state.set(GameState::Running);
 }
@@ -0,0 +1,139 @@
+use crate::{AppState, GoGameResource, board_input::BoardCursor, in_game::StonePos, rules};
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+/// Whether every group with a single liberty is outlined.
+#[derive(Resource, Debug, Default)]
+pub struct ShowAtari(pub bool);
+
+/// Ring child of a `StonePos` entity shown while its group is hovered.
+#[derive(Component)]
+pub struct GroupHighlight;
+
+/// Ring child of a `StonePos` entity shown while its group is in atari.
+#[derive(Component)]
+pub struct AtariOutline;
+
+#[derive(Component)]
+pub struct LibertyTooltip;
+
+/// Offset of the tooltip from the cursor so it does not hide the hovered stone.
+const TOOLTIP_OFFSET: Vec2 = Vec2::new(16.0, 16.0);
+
+pub fn setup_liberty_tooltip(mut commands: Commands) {
+    commands.spawn((
+        DespawnOnExit(AppState::InGame),
+        LibertyTooltip,
+        Node {
+            position_type: PositionType::Absolute,
+            padding: UiRect::all(px(5.)),
+            ..Default::default()
+        },
+        Text::default(),
+        TextColor(Color::WHITE),
+        TextFont {
+            font_size: 18.0,
+            ..Default::default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+        Visibility::Hidden,
+    ));
+}
+
+pub fn toggle_atari(input: Res<ButtonInput<KeyCode>>, mut show: ResMut<ShowAtari>) {
+    if input.just_pressed(KeyCode::KeyA) {
+        show.0 = !show.0;
+        log::debug!("Atari outlines: {}", show.0);
+    }
+}
+
+fn liberty_label(liberties: usize) -> String {
+    match liberties {
+        1 => "1 liberty (atari)".into(),
+        n => format!("{n} liberties"),
+    }
+}
+
+/// Highlights the group under the cursor and shows its liberties next to it.
+pub fn inspect_hovered_group(
+    cursor: BoardCursor,
+    game: Res<GoGameResource>,
+    stones: Query<(&StonePos, &Children)>,
+    mut highlights: Query<&mut Visibility, (With<GroupHighlight>, Without<LibertyTooltip>)>,
+    mut tooltip: Single<(&mut Node, &mut Text, &mut Visibility), With<LibertyTooltip>>,
+) {
+    let group = cursor
+        .intersection()
+        .and_then(|(x, y)| rules::group_at(&game.game, x, y));
+    let members = group
+        .as_ref()
+        .map(|group| group.stones.iter().copied().collect::<HashSet<_>>())
+        .unwrap_or_default();
+    for (pos, children) in &stones {
+        let visibility = if members.contains(&(pos.x, pos.y)) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        let mut iter = highlights.iter_many_mut(children);
+        while let Some(mut highlight) = iter.fetch_next() {
+            highlight.set_if_neq(visibility);
+        }
+    }
+
+    let (node, text, visibility) = &mut *tooltip;
+    match (group, cursor.window_position()) {
+        (Some(group), Some(position)) => {
+            let position = position + TOOLTIP_OFFSET;
+            node.left = px(position.x);
+            node.top = px(position.y);
+            let label = liberty_label(group.liberties.len());
+            if text.0 != label {
+                text.0 = label;
+            }
+            visibility.set_if_neq(Visibility::Visible);
+        }
+        _ => {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+    }
+}
+
+pub fn update_atari_outlines(
+    game: Res<GoGameResource>,
+    show: Res<ShowAtari>,
+    stones: Query<(&StonePos, &Children)>,
+    mut outlines: Query<&mut Visibility, With<AtariOutline>>,
+) {
+    log::trace!("Updating atari outlines");
+    let size = game.game.size();
+    let mut in_atari = HashSet::new();
+    if show.0 {
+        let mut visited = HashSet::new();
+        for y in 0..size {
+            for x in 0..size {
+                if visited.contains(&(x, y)) {
+                    continue;
+                }
+                let Some(group) = rules::group_at(&game.game, x, y) else {
+                    continue;
+                };
+                visited.extend(group.stones.iter().copied());
+                if group.liberties.len() == 1 {
+                    in_atari.extend(group.stones);
+                }
+            }
+        }
+    }
+    for (pos, children) in &stones {
+        let visibility = if in_atari.contains(&(pos.x, pos.y)) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        let mut iter = outlines.iter_many_mut(children);
+        while let Some(mut outline) = iter.fetch_next() {
+            outline.set_if_neq(visibility);
+        }
+    }
+}
@@ -1,17 +1,34 @@
+mod agent;
+mod analysis;
+mod board_input;
+mod clock;
+mod game_setup;
+pub mod gtp;
+mod hud;
+mod illegal_move;
 mod in_game;
+mod inspect;
+mod move_marks;
 mod pause;
+mod playback;
+pub mod rules;
+mod scoring;
 mod setting;
 mod setup;
 mod title;
+pub mod tournament;
 mod ui_button;
 
+pub use agent::{AgentRegistry, Decision, GoAgent, GtpAgent};
 use bevy::{prelude::*, window::WindowResized};
+use std::collections::HashSet;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, States)]
 #[states(scoped_entities)]
 enum AppState {
     #[default]
     Title,
+    GameSetup,
     InGame,
     Settings,
 }
@@ -22,6 +39,8 @@ enum AppState {
 enum GameState {
     #[default]
     Running,
+    /// Both players passed; dead stones are marked before the result is counted.
+    Scoring,
     GameOver,
     Paused,
 }
@@ -29,9 +48,236 @@ enum GameState {
 #[derive(Resource, Debug, Clone, Default)]
 struct GoGameResource {
     game: igo_core::Game,
+    turn: igo_core::Stone,
+    consecutive_passes: u8,
+    end: Option<GameEnd>,
+    komi: f32,
+    prisoners: Prisoners,
+    /// Position the move list is replayed from, including handicap stones.
+    initial: igo_core::Game,
+    /// Side to move in `initial`; White when Black received handicap stones.
+    first_turn: igo_core::Stone,
+    moves: Vec<igo_core::GameHand>,
+    /// Intersection each entry of `moves` was played on; `None` for passes.
+    positions: Vec<Option<(u8, u8)>>,
+    /// Undone hands, most recently undone last.
+    redo: Vec<igo_core::GameHand>,
+    /// Point the side to move may not retake immediately, while a ko is active.
+    ko: Option<(u8, u8)>,
+    /// Stones marked dead during scoring; they count as captured.
+    dead_stones: HashSet<(u8, u8)>,
+}
+
+/// Opponent stones each color has captured so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Prisoners {
+    black: u32,
+    white: u32,
+}
+
+/// How a game ended other than by both players passing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GameEnd {
+    /// The given color resigned.
+    Resigned(igo_core::Stone),
+    /// The given color ran out of time.
+    TimedOut(igo_core::Stone),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Controller {
+    #[default]
+    Human,
+    Ai,
+}
+
+/// Who decides the hands of each color.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+struct PlayerControllers {
+    black: Controller,
+    white: Controller,
+}
+
+impl Default for PlayerControllers {
+    fn default() -> Self {
+        Self {
+            black: Controller::Human,
+            white: Controller::Ai,
+        }
+    }
+}
+
+impl PlayerControllers {
+    fn get(&self, stone: igo_core::Stone) -> Controller {
+        match stone {
+            igo_core::Stone::Black => self.black,
+            igo_core::Stone::White => self.white,
+        }
+    }
+}
+
+/// Options chosen in the pre-game setup.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+struct GameSettings {
+    board_size: u8,
+    /// Black stones placed before the first hand; 0 for an even game.
+    handicap: u8,
+    komi: f32,
+    time_control: clock::TimeControl,
+    difficulty: configure::Difficulty,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            board_size: 19,
+            handicap: 0,
+            komi: rules::DEFAULT_KOMI,
+            time_control: clock::TimeControl::Unlimited,
+            difficulty: configure::Difficulty::default(),
+        }
+    }
+}
+
+/// Seed of the current game's RNG, fixed by the config or drawn per game.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+struct GameSeed {
+    configured: Option<u64>,
+    current: u64,
+}
+
+impl GameSeed {
+    fn next_game(&mut self) -> u64 {
+        self.current = self.configured.unwrap_or_else(rand::random);
+        self.current
+    }
+}
+
+impl GoGameResource {
+    fn new(settings: &GameSettings) -> Self {
+        let mut game = igo_core::Game::new(settings.board_size);
+        let handicap = rules::handicap_points(settings.board_size, settings.handicap);
+        for &(x, y) in &handicap {
+            match rules::find_hand(&game, igo_core::Stone::Black, x, y) {
+                Some(hand) => {
+                    game.put_hand(hand);
+                }
+                None => log::warn!("Cannot place handicap stone at ({x}, {y})"),
+            }
+        }
+        let first_turn = if handicap.is_empty() {
+            igo_core::Stone::default()
+        } else {
+            igo_core::Stone::White
+        };
+        Self {
+            initial: game.clone(),
+            game,
+            turn: first_turn,
+            first_turn,
+            komi: settings.komi,
+            ..Default::default()
+        }
+    }
+
+    fn reset(&mut self) {
+        self.game = self.initial.clone();
+        self.turn = self.first_turn;
+        self.consecutive_passes = 0;
+        self.end = None;
+        self.prisoners = Prisoners::default();
+        self.moves.clear();
+        self.positions.clear();
+        self.ko = None;
+        self.redo.clear();
+        self.dead_stones.clear();
+    }
+
+    /// Plays `hand` for the side to move and passes the turn.
+    /// Returns `false` once the game has ended, including after two passes in a row.
+    fn play(&mut self, hand: igo_core::GameHand) -> bool {
+        self.redo.clear();
+        self.apply(hand)
+    }
+
+    /// Takes back the last hand. Returns `false` when there is nothing to undo.
+    fn undo(&mut self) -> bool {
+        let Some(hand) = self.moves.pop() else {
+            return false;
+        };
+        log::debug!("Undo: {:?}", hand);
+        self.redo.push(hand);
+        let moves = std::mem::take(&mut self.moves);
+        self.game = self.initial.clone();
+        self.turn = self.first_turn;
+        self.consecutive_passes = 0;
+        self.prisoners = Prisoners::default();
+        self.positions.clear();
+        self.ko = None;
+        for hand in moves {
+            self.apply(hand);
+        }
+        true
+    }
+
+    /// Replays the last undone hand. Returns `false` when there is nothing to redo.
+    fn redo(&mut self) -> bool {
+        let Some(hand) = self.redo.pop() else {
+            return false;
+        };
+        log::debug!("Redo: {:?}", hand);
+        self.apply(hand);
+        true
+    }
+
+    fn apply(&mut self, hand: igo_core::GameHand) -> bool {
+        log::debug!("Hand: {:?}", hand);
+        self.moves.push(hand);
+        let position = rules::hand_position(&self.game, hand);
+        self.positions.push(position);
+        if position.is_none() {
+            self.consecutive_passes += 1;
+            log::info!("{:?} passes", self.turn);
+        } else {
+            self.consecutive_passes = 0;
+        }
+        let opponent = self.turn.opposite();
+        let before = rules::count_stones(&self.game, opponent);
+        let continues = self.game.put_hand(hand);
+        let captured = before.saturating_sub(rules::count_stones(&self.game, opponent)) as u32;
+        match self.turn {
+            igo_core::Stone::Black => self.prisoners.black += captured,
+            igo_core::Stone::White => self.prisoners.white += captured,
+        }
+        // A single stone that captured a single stone and is left with one
+        // liberty could be recaptured at once; that liberty is the ko point.
+        self.ko = position.filter(|_| captured == 1).and_then(|(x, y)| {
+            let group = rules::group_at(&self.game, x, y)?;
+            (group.stones.len() == 1 && group.liberties.len() == 1).then(|| group.liberties[0])
+        });
+        self.turn = opponent;
+        continues && self.consecutive_passes < 2
+    }
+
+    /// Territory-counting breakdown of `(black, white)`; White receives the komi.
+    fn score(&self) -> (rules::SideScore, rules::SideScore) {
+        rules::territory_score(
+            &self.game,
+            &self.dead_stones,
+            (self.prisoners.black, self.prisoners.white),
+            self.komi,
+        )
+    }
+
+    fn resign(&mut self, stone: igo_core::Stone) {
+        log::info!("{stone:?} resigns");
+        self.end = Some(GameEnd::Resigned(stone));
+    }
 }
 
 pub fn app() -> App {
+    let mut config = configure::Config::load(configure::CONFIG_PATH);
+    config.apply_args(std::env::args().skip(1));
     let mut app = App::new();
     app.add_plugins(
         DefaultPlugins
@@ -42,6 +288,24 @@ pub fn app() -> App {
     .add_sub_state::<GameState>()
     .insert_resource(ClearColor(Color::BLACK))
     .init_resource::<GoGameResource>()
+    .init_resource::<PlayerControllers>()
+    .insert_resource(GameSettings {
+        difficulty: config.difficulty,
+        ..Default::default()
+    })
+    .insert_resource(GameSeed {
+        configured: config.seed,
+        current: 0,
+    })
+    .insert_resource(agent::AgentRegistry::from_config(&config))
+    .insert_resource(agent::SelectedAgents::for_difficulty(config.difficulty))
+    .init_resource::<playback::PlaybackSpeed>()
+    .init_resource::<playback::SingleStep>()
+    .init_resource::<move_marks::ShowMoveNumbers>()
+    .init_resource::<inspect::ShowAtari>()
+    .init_resource::<analysis::ShowAnalysis>()
+    .init_resource::<analysis::AnalysisSearch>()
+    .add_message::<illegal_move::IllegalMoveAttempt>()
     .add_plugins(fps_counter::FpsCounterPlugin::default())
     .add_systems(
         Startup,
@@ -59,18 +323,147 @@ pub fn app() -> App {
         ui_button::button_interaction_event.after(bevy::ui::ui_focus_system),
     )
     .add_systems(OnEnter(AppState::Title), title::setup_title_ui)
-    .add_systems(OnEnter(AppState::InGame), in_game::setup_in_game_ui)
+    .add_systems(
+        OnEnter(AppState::GameSetup),
+        game_setup::setup_game_setup_ui,
+    )
+    .add_systems(
+        Update,
+        game_setup::back_to_title.run_if(in_state(AppState::GameSetup)),
+    )
+    .add_systems(
+        OnEnter(AppState::InGame),
+        (
+            in_game::setup_in_game_ui,
+            in_game::setup_in_game_controls,
+            agent::setup_agents,
+            clock::setup_clock,
+            clock::setup_clock_ui,
+            hud::setup_hud,
+            move_marks::setup_pass_indicator,
+            inspect::setup_liberty_tooltip,
+            illegal_move::setup_illegal_move_message,
+        ),
+    )
+    .add_systems(
+        Update,
+        pause::toggle_pause.run_if(in_state(GameState::Running).or(in_state(GameState::Paused))),
+    )
+    .add_systems(
+        Update,
+        playback::apply_playback_speed.run_if(resource_changed::<playback::PlaybackSpeed>),
+    )
+    .add_systems(
+        Update,
+        (in_game::update_in_game, clock::update_clock_text).run_if(in_state(AppState::InGame)),
+    )
+    .add_systems(
+        Update,
+        hud::update_hud.run_if(in_state(AppState::InGame).and(resource_changed::<GoGameResource>)),
+    )
+    .add_systems(
+        Update,
+        (
+            move_marks::toggle_move_numbers,
+            move_marks::update_move_marks.run_if(
+                resource_changed::<GoGameResource>
+                    .or(resource_changed::<move_marks::ShowMoveNumbers>),
+            ),
+        )
+            .chain()
+            .run_if(in_state(AppState::InGame)),
+    )
     .add_systems(
         Update,
-        pause::toggle_pause
-            .run_if(in_state(AppState::InGame).and(not(in_state(GameState::GameOver)))),
+        (
+            inspect::inspect_hovered_group,
+            inspect::toggle_atari,
+            inspect::update_atari_outlines.run_if(
+                resource_changed::<GoGameResource>.or(resource_changed::<inspect::ShowAtari>),
+            ),
+        )
+            .chain()
+            .run_if(in_state(AppState::InGame)),
     )
     .add_systems(
         Update,
-        (in_game::update_in_game, in_game::tick_game)
+        (
+            illegal_move::show_illegal_move.run_if(on_message::<illegal_move::IllegalMoveAttempt>),
+            illegal_move::fade_illegal_move_feedback,
+            illegal_move::update_ko_marker.run_if(resource_changed::<GoGameResource>),
+        )
+            .chain()
+            .run_if(in_state(AppState::InGame)),
+    )
+    .add_systems(
+        Update,
+        (
+            analysis::toggle_analysis,
+            analysis::restart_analysis.run_if(
+                resource_changed::<GoGameResource>
+                    .or(resource_changed::<analysis::ShowAnalysis>)
+                    .or(state_changed::<GameState>),
+            ),
+            analysis::draw_analysis,
+        )
+            .chain()
+            .run_if(in_state(AppState::InGame)),
+    )
+    .add_systems(OnExit(AppState::InGame), analysis::stop_analysis)
+    .add_systems(
+        Update,
+        clock::tick_clock.run_if(in_state(AppState::InGame).and(in_state(GameState::Running))),
+    )
+    .add_systems(
+        Update,
+        (
+            board_input::preview_ghost_stone.after(in_game::update_in_game),
+            board_input::place_stone_on_click,
+            playback::control_playback,
+            in_game::undo_redo_keys,
+        )
             .run_if(in_state(AppState::InGame).and(in_state(GameState::Running))),
     )
-    .add_systems(OnEnter(GameState::GameOver), in_game::setup_game_over_ui)
+    .add_systems(
+        Update,
+        playback::request_step.run_if(in_state(AppState::InGame).and(in_state(GameState::Paused))),
+    )
+    .add_systems(
+        FixedUpdate,
+        in_game::tick_game.run_if(
+            in_state(AppState::InGame).and(
+                in_state(GameState::Running)
+                    .or(in_state(GameState::Paused).and(playback::step_requested)),
+            ),
+        ),
+    )
+    .add_systems(
+        OnEnter(GameState::Scoring),
+        (
+            scoring::setup_scoring,
+            scoring::setup_scoring_ui,
+            scoring::draw_territory,
+        )
+            .chain(),
+    )
+    .add_systems(
+        Update,
+        (
+            scoring::toggle_dead_group,
+            scoring::update_accept_buttons.run_if(resource_changed::<scoring::ScoreAcceptance>),
+            scoring::draw_territory.run_if(resource_changed::<GoGameResource>),
+            scoring::finish_scoring,
+        )
+            .chain()
+            .run_if(in_state(GameState::Scoring)),
+    )
+    .add_systems(
+        OnEnter(GameState::GameOver),
+        (
+            in_game::setup_game_over_ui,
+            scoring::draw_territory.run_if(scoring::decided_by_counting),
+        ),
+    )
     .add_systems(
         OnEnter(GameState::Paused),
         pause::setup_pause.run_if(in_state(AppState::InGame)),
@@ -81,3 +474,65 @@ pub fn app() -> App {
     );
     app
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use igo_core::Stone;
+
+    fn play_at(game: &mut GoGameResource, x: u8, y: u8) {
+        let hand = rules::find_hand(&game.game, game.turn, x, y).expect("point is playable");
+        assert!(game.play(hand));
+    }
+
+    /// 5x5 board split by a Black wall on column 2 and a White wall on
+    /// column 3, with a lone White stone left inside Black's area.
+    fn split_board() -> GoGameResource {
+        let mut game = GoGameResource::new(&GameSettings {
+            board_size: 5,
+            komi: 6.5,
+            ..Default::default()
+        });
+        for y in 0..5 {
+            play_at(&mut game, 2, y);
+            play_at(&mut game, 3, y);
+        }
+        assert!(game.play(igo_core::GameHand::pass(Stone::Black)));
+        play_at(&mut game, 0, 0);
+        game
+    }
+
+    #[test]
+    fn live_invader_leaves_the_area_neutral() {
+        let game = split_board();
+        let (black, white) = game.score();
+        assert_eq!((black.territory, black.prisoners), (0, 0));
+        assert_eq!((white.territory, white.prisoners, white.komi), (5, 0, 6.5));
+    }
+
+    #[test]
+    fn dead_stones_count_as_prisoners_and_territory() {
+        let mut game = split_board();
+        game.dead_stones.insert((0, 0));
+        let (black, white) = game.score();
+        assert_eq!((black.territory, black.prisoners, black.komi), (10, 1, 0.));
+        assert_eq!((white.territory, white.prisoners), (5, 0));
+        assert_eq!(black.total() - white.total(), -0.5);
+    }
+
+    #[test]
+    fn captures_count_as_prisoners() {
+        let mut game = GoGameResource::new(&GameSettings {
+            board_size: 5,
+            ..Default::default()
+        });
+        // White's corner stone at (0, 0) is captured by Black's (0, 1).
+        play_at(&mut game, 1, 0);
+        play_at(&mut game, 0, 0);
+        play_at(&mut game, 0, 1);
+        assert_eq!(game.prisoners.black, 1);
+        assert_eq!(game.score().0.prisoners, 1);
+        game.undo();
+        assert_eq!(game.prisoners.black, 0);
+    }
+}
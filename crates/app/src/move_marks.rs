@@ -0,0 +1,117 @@
+use crate::{
+    AppState, GoGameResource,
+    in_game::{BoardLayout, StonePos},
+};
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+/// Whether stones show the number of the move that placed them.
+#[derive(Resource, Debug, Default)]
+pub struct ShowMoveNumbers(pub bool);
+
+/// Ring drawn on the most recently played stone.
+#[derive(Component)]
+pub struct LastMoveMarker;
+
+/// Text child of a `StonePos` entity holding its move number.
+#[derive(Component)]
+pub struct MoveNumber;
+
+#[derive(Component)]
+pub struct PassIndicator;
+
+type MoveNumberTexts<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Text2d,
+        &'static mut TextColor,
+        &'static mut Visibility,
+    ),
+    (With<MoveNumber>, Without<LastMoveMarker>),
+>;
+
+pub fn setup_pass_indicator(mut commands: Commands) {
+    commands.spawn((
+        DespawnOnExit(AppState::InGame),
+        PassIndicator,
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            top: px(10.0),
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+        Text::default(),
+        TextColor(Color::WHITE),
+        TextLayout {
+            justify: Justify::Center,
+            ..Default::default()
+        },
+        TextFont {
+            font_size: 30.0,
+            ..Default::default()
+        },
+    ));
+}
+
+pub fn toggle_move_numbers(input: Res<ButtonInput<KeyCode>>, mut show: ResMut<ShowMoveNumbers>) {
+    if input.just_pressed(KeyCode::KeyM) {
+        show.0 = !show.0;
+        log::debug!("Move numbers: {}", show.0);
+    }
+}
+
+pub fn update_move_marks(
+    game: Res<GoGameResource>,
+    show: Res<ShowMoveNumbers>,
+    layout: Res<BoardLayout>,
+    mut marker: Single<(&mut Transform, &mut Visibility), With<LastMoveMarker>>,
+    mut pass: Single<&mut Text, With<PassIndicator>>,
+    stones: Query<(&StonePos, &Children)>,
+    mut numbers: MoveNumberTexts,
+) {
+    log::trace!("Updating move marks");
+    let (transform, visibility) = &mut *marker;
+    pass.0.clear();
+    match game.positions.last() {
+        Some(Some((x, y))) => {
+            transform.translation = layout.position(*x, *y).extend(1.);
+            **visibility = Visibility::Visible;
+        }
+        Some(None) => {
+            pass.0 = format!("{:?} passes", game.turn.opposite());
+            **visibility = Visibility::Hidden;
+        }
+        None => **visibility = Visibility::Hidden,
+    }
+
+    // A point can be played more than once after captures; the latest move wins.
+    let move_numbers = game
+        .positions
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, position)| Some(((*position)?, idx + 1)))
+        .collect::<HashMap<_, _>>();
+    let board = game.game.board();
+    for (pos, children) in &stones {
+        let number = move_numbers.get(&(pos.x, pos.y));
+        for &child in children {
+            let Ok((mut text, mut color, mut visibility)) = numbers.get_mut(child) else {
+                continue;
+            };
+            // Captured points keep their number but show it only once replayed.
+            match (show.0, number, board.get_stone(pos.x, pos.y)) {
+                (true, Some(number), Some(stone)) => {
+                    text.0 = number.to_string();
+                    color.0 = match stone {
+                        igo_core::Stone::Black => Color::WHITE,
+                        igo_core::Stone::White => Color::BLACK,
+                    };
+                    *visibility = Visibility::Inherited;
+                }
+                _ => *visibility = Visibility::Hidden,
+            }
+        }
+    }
+}
@@ -9,8 +9,11 @@ pub fn setup_pause(mut commands: Commands) {
     commands
         .spawn((
             DespawnOnExit(GameState::Paused),
+            // A side panel keeps the board visible while stepping through hands.
             Node {
-                width: percent(100),
+                position_type: PositionType::Absolute,
+                right: px(0.0),
+                width: percent(22),
                 height: percent(100),
                 align_items: AlignItems::Center,
                 justify_content: JustifyContent::Center,
@@ -25,6 +28,17 @@ pub fn setup_pause(mut commands: Commands) {
             p.spawn((create_button("Resume"),)).observe(on_resume);
             p.spawn((create_button("Back To Title"),))
                 .observe(on_back_to_title);
+            p.spawn((
+                Text(
+                    "N: step one move / +, -: speed / F: fast forward / M: move numbers / A: atari / H: analysis"
+                        .into(),
+                ),
+                TextColor(Color::WHITE),
+                TextFont {
+                    font_size: 16.0,
+                    ..Default::default()
+                },
+            ));
         });
 }
 
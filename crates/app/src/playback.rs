@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+
+const DEFAULT_MOVES_PER_SECOND: f64 = 2.;
+const MIN_MOVES_PER_SECOND: f64 = 0.25;
+const MAX_MOVES_PER_SECOND: f64 = 32.;
+const FAST_FORWARD_MOVES_PER_SECOND: f64 = 240.;
+
+/// How fast AI hands are applied; `tick_game` runs once per fixed timestep.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PlaybackSpeed {
+    pub moves_per_second: f64,
+    pub fast_forward: bool,
+}
+
+impl Default for PlaybackSpeed {
+    fn default() -> Self {
+        Self {
+            moves_per_second: DEFAULT_MOVES_PER_SECOND,
+            fast_forward: false,
+        }
+    }
+}
+
+/// Set while paused to let `tick_game` apply exactly one more hand.
+#[derive(Resource, Debug, Default)]
+pub struct SingleStep(pub bool);
+
+pub fn step_requested(step: Res<SingleStep>) -> bool {
+    step.0
+}
+
+pub fn control_playback(input: Res<ButtonInput<KeyCode>>, mut speed: ResMut<PlaybackSpeed>) {
+    if input.just_pressed(KeyCode::Equal) {
+        speed.moves_per_second = (speed.moves_per_second * 2.).min(MAX_MOVES_PER_SECOND);
+    }
+    if input.just_pressed(KeyCode::Minus) {
+        speed.moves_per_second = (speed.moves_per_second / 2.).max(MIN_MOVES_PER_SECOND);
+    }
+    if input.just_pressed(KeyCode::KeyF) {
+        speed.fast_forward = !speed.fast_forward;
+    }
+}
+
+pub fn request_step(input: Res<ButtonInput<KeyCode>>, mut step: ResMut<SingleStep>) {
+    if input.just_pressed(KeyCode::KeyN) {
+        log::trace!("Stepping one move");
+        step.0 = true;
+    }
+}
+
+pub fn apply_playback_speed(speed: Res<PlaybackSpeed>, mut time: ResMut<Time<Fixed>>) {
+    let hz = if speed.fast_forward {
+        FAST_FORWARD_MOVES_PER_SECOND
+    } else {
+        speed.moves_per_second
+    };
+    log::info!(
+        "Playback: {} moves/s{}",
+        speed.moves_per_second,
+        if speed.fast_forward {
+            " (fast forward)"
+        } else {
+            ""
+        }
+    );
+    time.set_timestep_hz(hz);
+}
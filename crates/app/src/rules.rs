@@ -0,0 +1,493 @@
+use igo_core::{Game, GameHand, Stone};
+use std::collections::HashSet;
+
+pub const DEFAULT_KOMI: f32 = 6.5;
+
+/// Column letters used to call out moves; `I` is skipped to avoid confusion with `J`.
+const COLUMN_NAMES: &[u8] = b"ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+/// Letter naming column `x`, e.g. `J` for the ninth column.
+pub fn column_name(x: u8) -> char {
+    COLUMN_NAMES.get(x as usize).map_or('?', |&c| c as char)
+}
+
+/// Number naming row `y` (counted from the top) on a board of `size` lines;
+/// rows are numbered from 1 at the bottom.
+pub fn row_name(y: u8, size: u8) -> u8 {
+    size - y
+}
+
+/// Standard star points (hoshi) for the common board sizes.
+pub fn star_points(size: u8) -> &'static [(u8, u8)] {
+    match size {
+        9 => &[(2, 2), (6, 2), (4, 4), (2, 6), (6, 6)],
+        13 => &[(3, 3), (9, 3), (6, 6), (3, 9), (9, 9)],
+        19 => &[
+            (3, 3),
+            (9, 3),
+            (15, 3),
+            (3, 9),
+            (9, 9),
+            (15, 9),
+            (3, 15),
+            (9, 15),
+            (15, 15),
+        ],
+        _ => &[],
+    }
+}
+
+/// Returns the intersection `hand` would occupy if it were played on `game`.
+///
+/// `None` means the hand does not put a stone on the board, i.e. it is a pass.
+pub fn hand_position(game: &Game, hand: GameHand) -> Option<(u8, u8)> {
+    let mut next = game.clone();
+    next.put_hand(hand);
+    let (before, after) = (game.board(), next.board());
+    let size = game.size();
+    (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .find(|&(x, y)| before.get_stone(x, y).is_none() && after.get_stone(x, y).is_some())
+}
+
+/// Why a stone cannot be played on an intersection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalMove {
+    Occupied,
+    Suicide,
+    Ko,
+}
+
+impl std::fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IllegalMove::Occupied => write!(f, "the point is occupied"),
+            IllegalMove::Suicide => write!(f, "suicide is not allowed"),
+            IllegalMove::Ko => write!(f, "ko must not be retaken immediately"),
+        }
+    }
+}
+
+/// Classifies why `stone` has no allowed hand on `(x, y)`.
+///
+/// A stone that would keep a liberty or capture something is only forbidden
+/// by the ko rule, so anything neither occupied nor suicide is reported as ko.
+pub fn illegal_reason(game: &Game, stone: Stone, x: u8, y: u8) -> IllegalMove {
+    let board = game.board();
+    if board.get_stone(x, y).is_some() {
+        return IllegalMove::Occupied;
+    }
+    let breathes = neighbors(x, y, game.size()).any(|(nx, ny)| {
+        let liberties = group_at(game, nx, ny).map(|group| group.liberties.len());
+        match board.get_stone(nx, ny) {
+            None => true,
+            Some(other) if other == stone => liberties > Some(1),
+            Some(_) => liberties == Some(1),
+        }
+    });
+    if breathes {
+        IllegalMove::Ko
+    } else {
+        IllegalMove::Suicide
+    }
+}
+
+/// Finds the allowed hand for `stone` that plays on `(x, y)`.
+pub fn find_hand(game: &Game, stone: Stone, x: u8, y: u8) -> Option<GameHand> {
+    game.get_allowed_hands(stone)
+        .iter()
+        .copied()
+        .find(|&hand| hand_position(game, hand) == Some((x, y)))
+}
+
+/// Orthogonal neighbours of `(x, y)` that lie on a board of `size` lines.
+pub fn neighbors(x: u8, y: u8, size: u8) -> impl Iterator<Item = (u8, u8)> {
+    [
+        x.checked_sub(1).map(|x| (x, y)),
+        (x + 1 < size).then_some((x + 1, y)),
+        y.checked_sub(1).map(|y| (x, y)),
+        (y + 1 < size).then_some((x, y + 1)),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+/// Area score of `(black, white)`: stones on the board plus the empty regions
+/// that touch only that color.
+pub fn area_score(game: &Game) -> (u32, u32) {
+    area_score_with_dead(game, &HashSet::new())
+}
+
+/// [`area_score`] with the stones on `dead` removed from the board first.
+pub fn area_score_with_dead(game: &Game, dead: &HashSet<(u8, u8)>) -> (u32, u32) {
+    let size = game.size();
+    let board = game.board();
+    let owners = territory(game, dead);
+    let (mut black, mut white) = (0, 0);
+    for y in 0..size {
+        for x in 0..size {
+            let alive = board.get_stone(x, y).filter(|_| !dead.contains(&(x, y)));
+            match alive.or(owners[y as usize * size as usize + x as usize]) {
+                Some(Stone::Black) => black += 1,
+                Some(Stone::White) => white += 1,
+                None => {}
+            }
+        }
+    }
+    (black, white)
+}
+
+/// Owner of every intersection, indexed by `y * size + x`: empty points (and
+/// points of `dead` stones) belong to a color when their region touches only
+/// that color. Points holding live stones and neutral points are `None`.
+pub fn territory(game: &Game, dead: &HashSet<(u8, u8)>) -> Vec<Option<Stone>> {
+    let size = game.size();
+    let board = game.board();
+    let stone_at = |x: u8, y: u8| board.get_stone(x, y).filter(|_| !dead.contains(&(x, y)));
+    let idx = |x: u8, y: u8| y as usize * size as usize + x as usize;
+    let mut owners = vec![None; size as usize * size as usize];
+    let mut visited = vec![false; size as usize * size as usize];
+    for y in 0..size {
+        for x in 0..size {
+            if stone_at(x, y).is_some() || visited[idx(x, y)] {
+                continue;
+            }
+            let mut region = Vec::new();
+            let (mut touches_black, mut touches_white) = (false, false);
+            let mut stack = vec![(x, y)];
+            visited[idx(x, y)] = true;
+            while let Some((px, py)) = stack.pop() {
+                region.push((px, py));
+                for (nx, ny) in neighbors(px, py, size) {
+                    match stone_at(nx, ny) {
+                        Some(Stone::Black) => touches_black = true,
+                        Some(Stone::White) => touches_white = true,
+                        None if !visited[idx(nx, ny)] => {
+                            visited[idx(nx, ny)] = true;
+                            stack.push((nx, ny));
+                        }
+                        None => {}
+                    }
+                }
+            }
+            let owner = match (touches_black, touches_white) {
+                (true, false) => Some(Stone::Black),
+                (false, true) => Some(Stone::White),
+                _ => None,
+            };
+            for (px, py) in region {
+                owners[idx(px, py)] = owner;
+            }
+        }
+    }
+    owners
+}
+
+/// One side's points under territory counting.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SideScore {
+    pub territory: u32,
+    /// Stones captured during play plus the opponent's dead stones.
+    pub prisoners: u32,
+    pub komi: f32,
+}
+
+impl SideScore {
+    pub fn total(&self) -> f32 {
+        (self.territory + self.prisoners) as f32 + self.komi
+    }
+}
+
+/// Territory-counting breakdown of `(black, white)`, the rule the app and the
+/// GTP server score by. `captured` holds the stones each color captured
+/// during play; the stones on `dead` count for their opponent, and White
+/// receives the komi.
+pub fn territory_score(
+    game: &Game,
+    dead: &HashSet<(u8, u8)>,
+    captured: (u32, u32),
+    komi: f32,
+) -> (SideScore, SideScore) {
+    let owners = territory(game, dead);
+    let owned = |stone| owners.iter().filter(|&&owner| owner == Some(stone)).count() as u32;
+    let board = game.board();
+    let dead = |stone| {
+        dead.iter()
+            .filter(|&&(x, y)| board.get_stone(x, y) == Some(stone))
+            .count() as u32
+    };
+    (
+        SideScore {
+            territory: owned(Stone::Black),
+            prisoners: captured.0 + dead(Stone::White),
+            komi: 0.,
+        },
+        SideScore {
+            territory: owned(Stone::White),
+            prisoners: captured.1 + dead(Stone::Black),
+            komi,
+        },
+    )
+}
+
+/// Points for `count` handicap stones on a board of `size` lines, in the
+/// traditional order: opposite corners first, then the remaining corners,
+/// the side star points and the center (which is used for odd counts).
+pub fn handicap_points(size: u8, count: u8) -> Vec<(u8, u8)> {
+    let near = if size < 13 { 2 } else { 3 };
+    let (far, mid) = (size - 1 - near, size / 2);
+    let corners = [(far, near), (near, far), (far, far), (near, near)];
+    let (left, right, top, bottom) = ((near, mid), (far, mid), (mid, near), (mid, far));
+    let center = (mid, mid);
+    let mut points = match count {
+        0..=1 => Vec::new(),
+        2..=4 => corners[..count as usize].to_vec(),
+        5 => corners.to_vec(),
+        6..=7 => [&corners[..], &[left, right]].concat(),
+        _ => [&corners[..], &[left, right, top, bottom]].concat(),
+    };
+    if count >= 5 && count % 2 == 1 {
+        points.push(center);
+    }
+    points
+}
+
+/// Diagonal neighbours of `(x, y)` that lie on a board of `size` lines.
+fn diagonals(x: u8, y: u8, size: u8) -> impl Iterator<Item = (u8, u8)> {
+    let (left, up) = (x.checked_sub(1), y.checked_sub(1));
+    let right = (x + 1 < size).then_some(x + 1);
+    let down = (y + 1 < size).then_some(y + 1);
+    [(left, up), (right, up), (left, down), (right, down)]
+        .into_iter()
+        .filter_map(|(x, y)| Some((x?, y?)))
+}
+
+/// A chain of connected stones of one color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub stone: Stone,
+    pub stones: Vec<(u8, u8)>,
+    pub liberties: Vec<(u8, u8)>,
+}
+
+/// Collects the group containing the stone at `(x, y)`.
+pub fn group_at(game: &Game, x: u8, y: u8) -> Option<Group> {
+    let board = game.board();
+    let size = game.size();
+    let stone = board.get_stone(x, y)?;
+    let idx = |x: u8, y: u8| y as usize * size as usize + x as usize;
+    let mut visited = vec![false; size as usize * size as usize];
+    let mut group = Group {
+        stone,
+        stones: Vec::new(),
+        liberties: Vec::new(),
+    };
+    let mut stack = vec![(x, y)];
+    visited[idx(x, y)] = true;
+    while let Some((px, py)) = stack.pop() {
+        group.stones.push((px, py));
+        for (nx, ny) in neighbors(px, py, size) {
+            if visited[idx(nx, ny)] {
+                continue;
+            }
+            match board.get_stone(nx, ny) {
+                None => {
+                    visited[idx(nx, ny)] = true;
+                    group.liberties.push((nx, ny));
+                }
+                Some(other) if other == stone => {
+                    visited[idx(nx, ny)] = true;
+                    stack.push((nx, ny));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    Some(group)
+}
+
+/// Whether the empty point `(x, y)` is a single-point eye of `stone`.
+///
+/// Every neighbour must be `stone`, and the diagonals may hold at most one
+/// opposing stone (none on the edge) so that false eyes are not counted.
+pub fn is_eye(game: &Game, x: u8, y: u8, stone: Stone) -> bool {
+    let board = game.board();
+    let size = game.size();
+    if board.get_stone(x, y).is_some()
+        || !neighbors(x, y, size).all(|(nx, ny)| board.get_stone(nx, ny) == Some(stone))
+    {
+        return false;
+    }
+    let (mut corners, mut opposing) = (0, 0);
+    for (dx, dy) in diagonals(x, y, size) {
+        corners += 1;
+        if board.get_stone(dx, dy) == Some(stone.opposite()) {
+            opposing += 1;
+        }
+    }
+    if corners < 4 {
+        opposing == 0
+    } else {
+        opposing <= 1
+    }
+}
+
+/// Number of `stone` stones on the board.
+pub fn count_stones(game: &Game, stone: Stone) -> usize {
+    let board = game.board();
+    let size = game.size();
+    (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .filter(|&(x, y)| board.get_stone(x, y) == Some(stone))
+        .count()
+}
+
+/// Groups with at most this many liberties may be proposed dead.
+const DEAD_GROUP_LIBERTIES: usize = 4;
+
+/// Stones an agent proposes to mark dead once both players have passed.
+///
+/// A group is proposed when it has fewer than two eyes, few liberties, and
+/// every one of its stones would become the opponent's territory once removed.
+pub fn propose_dead_stones(game: &Game) -> HashSet<(u8, u8)> {
+    let size = game.size();
+    let idx = |x: u8, y: u8| y as usize * size as usize + x as usize;
+    let mut seen = HashSet::new();
+    let mut dead = HashSet::new();
+    for y in 0..size {
+        for x in 0..size {
+            if seen.contains(&(x, y)) {
+                continue;
+            }
+            let Some(group) = group_at(game, x, y) else {
+                continue;
+            };
+            seen.extend(group.stones.iter().copied());
+            let eyes = group
+                .liberties
+                .iter()
+                .filter(|&&(lx, ly)| is_eye(game, lx, ly, group.stone))
+                .count();
+            if eyes >= 2 || group.liberties.len() > DEAD_GROUP_LIBERTIES {
+                continue;
+            }
+            let removed = group.stones.iter().copied().collect();
+            let owners = territory(game, &removed);
+            if group
+                .stones
+                .iter()
+                .all(|&(sx, sy)| owners[idx(sx, sy)] == Some(group.stone.opposite()))
+            {
+                dead.extend(group.stones);
+            }
+        }
+    }
+    dead
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(game: &mut Game, stone: Stone, points: &[(u8, u8)]) {
+        for &(x, y) in points {
+            let hand = find_hand(game, stone, x, y).expect("point is playable");
+            game.put_hand(hand);
+        }
+    }
+
+    #[test]
+    fn column_names_skip_i() {
+        let names = (0..19).map(column_name).collect::<String>();
+        assert_eq!(names, "ABCDEFGHJKLMNOPQRST");
+    }
+
+    #[test]
+    fn rows_count_from_the_bottom() {
+        assert_eq!(row_name(0, 19), 19);
+        assert_eq!(row_name(18, 19), 1);
+    }
+
+    #[test]
+    fn star_points_per_size() {
+        assert_eq!(star_points(9).len(), 5);
+        assert_eq!(star_points(13).len(), 5);
+        assert_eq!(star_points(19).len(), 9);
+        assert!(star_points(7).is_empty());
+        for size in [9, 13, 19] {
+            let center = (size / 2, size / 2);
+            assert!(star_points(size).contains(&center));
+        }
+    }
+
+    #[test]
+    fn handicap_points_per_count() {
+        for size in [9, 13, 19] {
+            assert!(handicap_points(size, 0).is_empty());
+            assert!(handicap_points(size, 1).is_empty());
+            for count in 2..=9 {
+                let points = handicap_points(size, count);
+                assert_eq!(points.len(), count as usize, "{count} on {size}");
+                let distinct = points.iter().collect::<HashSet<_>>();
+                assert_eq!(distinct.len(), points.len(), "{count} on {size}");
+                assert!(points.iter().all(|&(x, y)| x < size && y < size));
+                let center = (size / 2, size / 2);
+                assert_eq!(points.contains(&center), count >= 5 && count % 2 == 1);
+            }
+            // The first two stones take opposite corners.
+            let [(ax, ay), (bx, by)] = handicap_points(size, 2)[..] else {
+                unreachable!()
+            };
+            assert_eq!((ax + bx, ay + by), (size - 1, size - 1));
+        }
+        for count in 2..=9 {
+            let points = handicap_points(19, count);
+            assert!(points.iter().all(|point| star_points(19).contains(point)));
+        }
+    }
+
+    #[test]
+    fn corner_eye_needs_an_empty_diagonal() {
+        let mut game = Game::new(9);
+        place(&mut game, Stone::Black, &[(1, 0), (0, 1)]);
+        assert!(is_eye(&game, 0, 0, Stone::Black));
+        assert!(!is_eye(&game, 0, 0, Stone::White));
+        place(&mut game, Stone::White, &[(1, 1)]);
+        assert!(!is_eye(&game, 0, 0, Stone::Black));
+    }
+
+    #[test]
+    fn center_eye_tolerates_one_opposing_diagonal() {
+        let mut game = Game::new(9);
+        place(&mut game, Stone::Black, &[(3, 4), (5, 4), (4, 3), (4, 5)]);
+        assert!(is_eye(&game, 4, 4, Stone::Black));
+        place(&mut game, Stone::White, &[(3, 3)]);
+        assert!(is_eye(&game, 4, 4, Stone::Black));
+        place(&mut game, Stone::White, &[(5, 5)]);
+        assert!(!is_eye(&game, 4, 4, Stone::Black));
+    }
+
+    #[test]
+    fn proposes_stones_inside_opponent_territory() {
+        let mut game = Game::new(5);
+        place(
+            &mut game,
+            Stone::Black,
+            &(0..5).map(|y| (2, y)).collect::<Vec<_>>(),
+        );
+        place(
+            &mut game,
+            Stone::White,
+            &(0..5).map(|y| (3, y)).collect::<Vec<_>>(),
+        );
+        place(&mut game, Stone::White, &[(0, 0)]);
+        assert_eq!(propose_dead_stones(&game), HashSet::from([(0, 0)]));
+    }
+
+    #[test]
+    fn occupied_point_is_not_an_eye() {
+        let mut game = Game::new(9);
+        place(&mut game, Stone::Black, &[(4, 4)]);
+        assert!(!is_eye(&game, 4, 4, Stone::Black));
+    }
+}
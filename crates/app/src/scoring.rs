@@ -0,0 +1,276 @@
+use crate::{
+    Controller, GameState, GoGameResource, PlayerControllers,
+    board_input::BoardCursor,
+    in_game::BoardLayout,
+    rules,
+    ui_button::{ButtonClicked, create_button, set_button_label},
+};
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+/// Which sides have agreed to the current dead-stone marking.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct ScoreAcceptance {
+    black: bool,
+    white: bool,
+}
+
+impl ScoreAcceptance {
+    fn get(&self, stone: igo_core::Stone) -> bool {
+        match stone {
+            igo_core::Stone::Black => self.black,
+            igo_core::Stone::White => self.white,
+        }
+    }
+
+    fn get_mut(&mut self, stone: igo_core::Stone) -> &mut bool {
+        match stone {
+            igo_core::Stone::Black => &mut self.black,
+            igo_core::Stone::White => &mut self.white,
+        }
+    }
+}
+
+/// Dead stones the AI proposed when scoring started.
+///
+/// The AI agrees to a marking as long as none of its own stones outside the
+/// proposal are marked dead.
+#[derive(Resource, Debug, Default)]
+pub struct DeadStoneProposal(HashSet<(u8, u8)>);
+
+impl DeadStoneProposal {
+    fn accepts(&self, game: &GoGameResource, stone: igo_core::Stone) -> bool {
+        let board = game.game.board();
+        game.dead_stones
+            .iter()
+            .filter(|&&(x, y)| board.get_stone(x, y) == Some(stone))
+            .all(|point| self.0.contains(point))
+    }
+}
+
+/// Small square on an empty intersection showing who owns it.
+#[derive(Component)]
+pub struct TerritoryMarker;
+
+/// Marks the button with which `0` accepts the marking.
+#[derive(Component)]
+pub struct AcceptButton(igo_core::Stone);
+
+/// Shows whether the AI playing `0` agrees to the marking.
+#[derive(Component)]
+pub struct AiAcceptText(igo_core::Stone);
+
+fn accept_label(stone: igo_core::Stone, accepted: bool) -> String {
+    if accepted {
+        format!("{stone:?}: Accepted")
+    } else {
+        format!("{stone:?}: Accept")
+    }
+}
+
+fn ai_accept_label(stone: igo_core::Stone, accepted: bool) -> String {
+    if accepted {
+        format!("{stone:?} (AI): Accepted")
+    } else {
+        format!("{stone:?} (AI): Disagrees")
+    }
+}
+
+/// Starts from the AI's proposal when an AI plays, or with nothing marked
+/// otherwise. Humans always have to accept the marking themselves.
+pub fn setup_scoring(
+    mut commands: Commands,
+    mut game: ResMut<GoGameResource>,
+    controllers: Res<PlayerControllers>,
+) {
+    log::info!("Marking dead stones");
+    let any_ai = [controllers.black, controllers.white].contains(&Controller::Ai);
+    let proposal = if any_ai {
+        rules::propose_dead_stones(&game.game)
+    } else {
+        HashSet::new()
+    };
+    log::debug!("Proposed dead stones: {proposal:?}");
+    game.dead_stones = proposal.clone();
+    commands.insert_resource(ScoreAcceptance {
+        black: controllers.black == Controller::Ai,
+        white: controllers.white == Controller::Ai,
+    });
+    commands.insert_resource(DeadStoneProposal(proposal));
+}
+
+pub fn setup_scoring_ui(
+    mut commands: Commands,
+    controllers: Res<PlayerControllers>,
+    acceptance: Res<ScoreAcceptance>,
+) {
+    log::trace!("Setting up scoring UI");
+    commands
+        .spawn((
+            DespawnOnExit(GameState::Scoring),
+            Node {
+                position_type: PositionType::Absolute,
+                left: px(40.0),
+                bottom: px(40.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: px(10.0),
+                ..Default::default()
+            },
+        ))
+        .with_children(|p| {
+            p.spawn((
+                Text("Click groups to mark them dead".into()),
+                TextColor(Color::WHITE),
+                TextFont {
+                    font_size: 20.0,
+                    ..Default::default()
+                },
+            ));
+            for stone in [igo_core::Stone::Black, igo_core::Stone::White] {
+                match controllers.get(stone) {
+                    Controller::Human => {
+                        p.spawn((
+                            AcceptButton(stone),
+                            create_button(&accept_label(stone, acceptance.get(stone))),
+                        ))
+                        .observe(on_accept);
+                    }
+                    Controller::Ai => {
+                        p.spawn((
+                            AiAcceptText(stone),
+                            Text(ai_accept_label(stone, acceptance.get(stone))),
+                            TextColor(Color::WHITE),
+                            TextFont {
+                                font_size: 20.0,
+                                ..Default::default()
+                            },
+                        ));
+                    }
+                }
+            }
+        });
+}
+
+fn on_accept(
+    event: On<ButtonClicked>,
+    buttons: Query<&AcceptButton>,
+    mut acceptance: ResMut<ScoreAcceptance>,
+) {
+    let Ok(AcceptButton(stone)) = buttons.get(event.event_target()) else {
+        return;
+    };
+    log::debug!("{stone:?} accepts the dead stones");
+    *acceptance.get_mut(*stone) = true;
+}
+
+/// Toggles the clicked group between alive and dead. Any change withdraws the
+/// humans' acceptance, and the AI re-checks the marking against its proposal.
+pub fn toggle_dead_group(
+    mouse: Res<ButtonInput<MouseButton>>,
+    cursor: BoardCursor,
+    controllers: Res<PlayerControllers>,
+    proposal: Res<DeadStoneProposal>,
+    mut game: ResMut<GoGameResource>,
+    mut acceptance: ResMut<ScoreAcceptance>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some((x, y)) = cursor.intersection() else {
+        return;
+    };
+    let Some(group) = rules::group_at(&game.game, x, y) else {
+        return;
+    };
+    let dead = group
+        .stones
+        .iter()
+        .all(|stone| game.dead_stones.contains(stone));
+    log::debug!(
+        "Marking {:?} group at ({x}, {y}) {}",
+        group.stone,
+        if dead { "alive" } else { "dead" }
+    );
+    for stone in group.stones {
+        if dead {
+            game.dead_stones.remove(&stone);
+        } else {
+            game.dead_stones.insert(stone);
+        }
+    }
+    for stone in [igo_core::Stone::Black, igo_core::Stone::White] {
+        *acceptance.get_mut(stone) =
+            controllers.get(stone) == Controller::Ai && proposal.accepts(&game, stone);
+    }
+}
+
+pub fn update_accept_buttons(
+    acceptance: Res<ScoreAcceptance>,
+    buttons: Query<(Entity, &AcceptButton)>,
+    ai_texts: Query<(Entity, &AiAcceptText)>,
+    children: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    for (entity, AcceptButton(stone)) in &buttons {
+        set_button_label(
+            entity,
+            &accept_label(*stone, acceptance.get(*stone)),
+            &children,
+            &mut texts,
+        );
+    }
+    for (entity, AiAcceptText(stone)) in &ai_texts {
+        if let Ok(mut text) = texts.get_mut(entity) {
+            text.0 = ai_accept_label(*stone, acceptance.get(*stone));
+        }
+    }
+}
+
+pub fn finish_scoring(acceptance: Res<ScoreAcceptance>, mut state: ResMut<NextState<GameState>>) {
+    if acceptance.black && acceptance.white {
+        log::info!("Dead stones accepted");
+        state.set(GameState::GameOver);
+    }
+}
+
+/// Run condition: the game ended by counting rather than by resignation or time.
+pub fn decided_by_counting(game: Res<GoGameResource>) -> bool {
+    game.end.is_none()
+}
+
+/// Redraws the territory markers for the current marking of dead stones.
+pub fn draw_territory(
+    mut commands: Commands,
+    game: Res<GoGameResource>,
+    layout: Res<BoardLayout>,
+    state: Res<State<GameState>>,
+    markers: Query<Entity, With<TerritoryMarker>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    log::trace!("Drawing territory");
+    for entity in &markers {
+        commands.entity(entity).despawn();
+    }
+    let square = meshes.add(Rectangle::from_length(layout.stone_radius * 0.6));
+    let black_material = materials.add(ColorMaterial::from(Color::BLACK));
+    let white_material = materials.add(ColorMaterial::from(Color::WHITE));
+    let size = layout.line_count;
+    let owners = rules::territory(&game.game, &game.dead_stones);
+    for y in 0..size {
+        for x in 0..size {
+            let material = match owners[y as usize * size as usize + x as usize] {
+                Some(igo_core::Stone::Black) => black_material.clone(),
+                Some(igo_core::Stone::White) => white_material.clone(),
+                None => continue,
+            };
+            commands.spawn((
+                DespawnOnExit(*state.get()),
+                TerritoryMarker,
+                Mesh2d(square.clone()),
+                MeshMaterial2d(material),
+                Transform::from_translation(layout.position(x, y).extend(2.)),
+            ));
+        }
+    }
+}
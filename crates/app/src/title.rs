@@ -30,8 +30,8 @@ pub fn setup_title_ui(mut commands: Commands) {
 }
 
 fn on_game_start(_event: On<ButtonClicked>, mut state: ResMut<NextState<AppState>>) {
-    log::trace!("Starting the game...");
-    state.set(AppState::InGame);
+    log::trace!("Opening game setup...");
+    state.set(AppState::GameSetup);
 }
 
 fn on_settings(_event: On<ButtonClicked>, mut state: ResMut<NextState<AppState>>) {
@@ -0,0 +1,413 @@
+//! Headless self-play between two registered agents, reported as JSON.
+
+use crate::{
+    GameSettings, GoGameResource,
+    agent::{AgentRegistry, Decision, GoAgent},
+    rules,
+};
+use bevy::platform::time::Instant;
+use igo_core::Stone;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::Serialize;
+use std::sync::atomic::AtomicBool;
+
+/// z-score of a two-sided 95% confidence interval.
+const Z_95: f64 = 1.96;
+
+/// What the `igo_tournament` binary plays; set from its command line.
+#[derive(Debug, Clone)]
+pub struct TournamentConfig {
+    pub agent_a: String,
+    pub agent_b: String,
+    pub games: u32,
+    pub seed: u64,
+    pub board_size: u8,
+    pub komi: f32,
+}
+
+impl Default for TournamentConfig {
+    fn default() -> Self {
+        Self {
+            agent_a: crate::agent::MctsAgent::NAME.into(),
+            agent_b: crate::agent::HeuristicAgent::NAME.into(),
+            games: 10,
+            seed: 0,
+            board_size: 9,
+            komi: rules::DEFAULT_KOMI,
+        }
+    }
+}
+
+impl TournamentConfig {
+    /// Parses `--a NAME --b NAME --games N --seed N --size N --komi K`.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{arg} expects a value"))?;
+            let invalid = || format!("invalid value for {arg}: {value}");
+            match arg.as_str() {
+                "--a" => config.agent_a = value.clone(),
+                "--b" => config.agent_b = value.clone(),
+                "--games" => config.games = value.parse().map_err(|_| invalid())?,
+                "--seed" => config.seed = value.parse().map_err(|_| invalid())?,
+                "--size" => config.board_size = value.parse().map_err(|_| invalid())?,
+                "--komi" => config.komi = value.parse().map_err(|_| invalid())?,
+                _ => return Err(format!("unknown argument: {arg}")),
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct AgentReport {
+    pub name: String,
+    pub wins: u32,
+    /// Wins plus half the draws, divided by the games that finished.
+    pub win_rate: f64,
+    pub moves: u32,
+    pub mean_move_ms: f64,
+    pub max_move_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TournamentReport {
+    pub games: u32,
+    pub seed: u64,
+    pub board_size: u8,
+    pub komi: f32,
+    pub draws: u32,
+    /// Games stopped at the move limit; they count neither as wins nor draws.
+    pub truncated: u32,
+    pub average_length: f64,
+    pub agent_a: AgentReport,
+    pub agent_b: AgentReport,
+    /// Elo of A minus Elo of B; `None` when one side won every game.
+    pub elo_difference: Option<f64>,
+    /// 95% confidence interval of `elo_difference`.
+    pub elo_interval: (Option<f64>, Option<f64>),
+}
+
+/// Elo difference implied by scoring `score` (0..=1) against the opponent.
+fn elo(score: f64) -> Option<f64> {
+    (score > 0. && score < 1.).then(|| -400. * (1. / score - 1.).log10())
+}
+
+/// 95% confidence interval of the Elo difference after scoring `score` over
+/// `games` games; a bound is `None` where the score interval reaches 0 or 1.
+fn elo_interval(score: f64, games: f64) -> (Option<f64>, Option<f64>) {
+    let error = (score * (1. - score) / games).sqrt();
+    (
+        elo((score - Z_95 * error).max(0.)),
+        elo((score + Z_95 * error).min(1.)),
+    )
+}
+
+/// Per-agent move timings in milliseconds.
+#[derive(Default)]
+struct Timings(Vec<f64>);
+
+/// How a single game ended.
+struct GameOutcome {
+    /// `None` for a draw.
+    winner: Option<Stone>,
+    moves: u32,
+    /// Whether the game was stopped at the move limit rather than finished.
+    truncated: bool,
+}
+
+/// Plays one game, A taking Black when `a_is_black`.
+fn play_game(
+    config: &TournamentConfig,
+    agents: [&mut Box<dyn GoAgent>; 2],
+    a_is_black: bool,
+    timings: &mut [Timings; 2],
+) -> GameOutcome {
+    let settings = GameSettings {
+        board_size: config.board_size,
+        komi: config.komi,
+        ..Default::default()
+    };
+    let mut game = GoGameResource::new(&settings);
+    let [a, b] = agents;
+    a.set_komi(config.komi);
+    b.set_komi(config.komi);
+    let (black, white) = if a_is_black { (a, b) } else { (b, a) };
+    let (black_timing, white_timing) = match timings {
+        [a, b] if a_is_black => (a, b),
+        [a, b] => (b, a),
+    };
+    // Bots that never pass still end: allow three hands per intersection.
+    let limit = config.board_size as usize * config.board_size as usize * 3;
+    let mut truncated = true;
+    while game.moves.len() < limit {
+        let (agent, timing) = match game.turn {
+            Stone::Black => (&mut *black, &mut *black_timing),
+            Stone::White => (&mut *white, &mut *white_timing),
+        };
+        let started = Instant::now();
+        let decision = agent.select_hand(&game.game, game.turn, &AtomicBool::new(false));
+        timing.0.push(started.elapsed().as_secs_f64() * 1000.);
+        let Decision::Play(hand) = decision else {
+            return GameOutcome {
+                winner: Some(game.turn.opposite()),
+                moves: game.moves.len() as u32,
+                truncated: false,
+            };
+        };
+        if !game.play(hand) {
+            truncated = false;
+            break;
+        }
+    }
+    // Scored as the app scores games between agents: the proposed dead
+    // stones count as prisoners.
+    game.dead_stones = rules::propose_dead_stones(&game.game);
+    let (black_score, white_score) = game.score();
+    let margin = black_score.total() - white_score.total();
+    let winner = if margin > 0. {
+        Some(Stone::Black)
+    } else if margin < 0. {
+        Some(Stone::White)
+    } else {
+        None
+    };
+    GameOutcome {
+        winner,
+        moves: game.moves.len() as u32,
+        truncated,
+    }
+}
+
+/// Plays `config.games` games, alternating colors, and summarizes them.
+pub fn run_tournament(
+    registry: &AgentRegistry,
+    config: &TournamentConfig,
+) -> Result<TournamentReport, String> {
+    let mut seeds = StdRng::seed_from_u64(config.seed);
+    let mut timings = [Timings::default(), Timings::default()];
+    let (mut wins, mut draws, mut truncated, mut total_moves) = ([0u32; 2], 0, 0, 0u64);
+    for index in 0..config.games {
+        let create = |name: &str, seed| {
+            registry
+                .create(name, seed)
+                .ok_or_else(|| format!("agent {name} is not registered"))
+        };
+        let mut a = create(&config.agent_a, seeds.random())?;
+        let mut b = create(&config.agent_b, seeds.random())?;
+        let a_is_black = index % 2 == 0;
+        let outcome = play_game(config, [&mut a, &mut b], a_is_black, &mut timings);
+        total_moves += outcome.moves as u64;
+        let winner_index = outcome
+            .winner
+            .map(|stone| usize::from((stone == Stone::Black) != a_is_black));
+        let result = if outcome.truncated {
+            truncated += 1;
+            "truncated at the move limit"
+        } else {
+            match winner_index {
+                Some(idx) => {
+                    wins[idx] += 1;
+                    if idx == 0 {
+                        config.agent_a.as_str()
+                    } else {
+                        config.agent_b.as_str()
+                    }
+                }
+                None => {
+                    draws += 1;
+                    "draw"
+                }
+            }
+        };
+        eprintln!(
+            "Game {}/{}: {result} in {} moves",
+            index + 1,
+            config.games,
+            outcome.moves
+        );
+    }
+
+    let games = config.games.max(1) as f64;
+    let finished = (config.games - truncated).max(1) as f64;
+    let score_a = (wins[0] as f64 + draws as f64 / 2.) / finished;
+    let report = |idx: usize, name: &str| {
+        let timing = &timings[idx].0;
+        AgentReport {
+            name: name.into(),
+            wins: wins[idx],
+            win_rate: (wins[idx] as f64 + draws as f64 / 2.) / finished,
+            moves: timing.len() as u32,
+            mean_move_ms: timing.iter().sum::<f64>() / timing.len().max(1) as f64,
+            max_move_ms: timing.iter().copied().fold(0., f64::max),
+        }
+    };
+    Ok(TournamentReport {
+        games: config.games,
+        seed: config.seed,
+        board_size: config.board_size,
+        komi: config.komi,
+        draws,
+        truncated,
+        average_length: total_moves as f64 / games,
+        agent_a: report(0, &config.agent_a),
+        agent_b: report(1, &config.agent_b),
+        elo_difference: elo(score_a),
+        elo_interval: elo_interval(score_a, finished),
+    })
+}
+
+/// Entry point of the `igo_tournament` binary.
+pub fn run() {
+    let config = match TournamentConfig::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{err}");
+            eprintln!(
+                "usage: igo_tournament [--a AGENT] [--b AGENT] [--games N] [--seed N] [--size N] [--komi K]"
+            );
+            std::process::exit(2);
+        }
+    };
+    let registry = AgentRegistry::default();
+    match run_tournament(&registry, &config) {
+        Ok(report) => match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("Cannot serialize the report: {err}");
+                std::process::exit(1);
+            }
+        },
+        Err(err) => {
+            let names = registry.names().collect::<Vec<_>>().join(", ");
+            eprintln!("{err}; registered agents: {names}");
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use igo_core::{Game, GameHand};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    struct Passer;
+
+    impl GoAgent for Passer {
+        fn select_hand(&mut self, _game: &Game, turn: Stone, _cancel: &AtomicBool) -> Decision {
+            Decision::Play(GameHand::pass(turn))
+        }
+    }
+
+    struct Resigner;
+
+    impl GoAgent for Resigner {
+        fn select_hand(&mut self, _game: &Game, _turn: Stone, _cancel: &AtomicBool) -> Decision {
+            Decision::Resign
+        }
+    }
+
+    fn registry() -> AgentRegistry {
+        let mut registry = AgentRegistry::default();
+        registry
+            .register("passer", |_| Box::new(Passer))
+            .register("resigner", |_| Box::new(Resigner));
+        registry
+    }
+
+    #[test]
+    fn elo_of_known_scores() {
+        assert_eq!(elo(0.5), Some(0.));
+        let elo_75 = elo(0.75).unwrap();
+        assert!((elo_75 - 190.85).abs() < 0.01, "{elo_75}");
+        assert!((elo(0.25).unwrap() + elo_75).abs() < 1e-9);
+        assert_eq!(elo(0.), None);
+        assert_eq!(elo(1.), None);
+    }
+
+    #[test]
+    fn elo_interval_surrounds_the_estimate() {
+        let (low, high) = elo_interval(0.5, 100.);
+        let (low, high) = (low.unwrap(), high.unwrap());
+        // Score 0.5 ± 1.96 * 0.05.
+        assert!((high - elo(0.598).unwrap()).abs() < 1e-9);
+        assert!((low + high).abs() < 1e-9);
+
+        let (low, high) = elo_interval(0.9, 10.);
+        assert!(low.unwrap() < elo(0.9).unwrap());
+        assert_eq!(high, None);
+
+        assert_eq!(elo_interval(1., 10.), (None, None));
+        assert_eq!(elo_interval(0., 10.), (None, None));
+    }
+
+    #[test]
+    fn from_args_reads_every_option() {
+        let config = TournamentConfig::from_args(args(
+            "--a Random --b Heuristic --games 4 --seed 7 --size 13 --komi 0.5",
+        ))
+        .unwrap();
+        assert_eq!(config.agent_a, "Random");
+        assert_eq!(config.agent_b, "Heuristic");
+        assert_eq!(
+            (config.games, config.seed, config.board_size, config.komi),
+            (4, 7, 13, 0.5)
+        );
+    }
+
+    #[test]
+    fn from_args_defaults_and_errors() {
+        let config = TournamentConfig::from_args(Vec::new()).unwrap();
+        assert_eq!((config.games, config.board_size), (10, 9));
+        let err = |line| TournamentConfig::from_args(args(line)).unwrap_err();
+        assert_eq!(err("--games"), "--games expects a value");
+        assert_eq!(err("--size big"), "invalid value for --size: big");
+        assert_eq!(err("--rounds 3"), "unknown argument: --rounds");
+    }
+
+    #[test]
+    fn passing_games_go_to_white_on_komi() {
+        let config = TournamentConfig {
+            agent_a: "passer".into(),
+            agent_b: "passer".into(),
+            games: 2,
+            board_size: 5,
+            ..Default::default()
+        };
+        let report = run_tournament(&registry(), &config).unwrap();
+        assert_eq!((report.agent_a.wins, report.agent_b.wins), (1, 1));
+        assert_eq!((report.draws, report.truncated), (0, 0));
+        assert_eq!(report.average_length, 2.);
+        assert_eq!(report.elo_difference, Some(0.));
+    }
+
+    #[test]
+    fn resigning_loses_the_game() {
+        let config = TournamentConfig {
+            agent_a: "resigner".into(),
+            agent_b: "passer".into(),
+            games: 2,
+            board_size: 5,
+            ..Default::default()
+        };
+        let report = run_tournament(&registry(), &config).unwrap();
+        assert_eq!((report.agent_a.wins, report.agent_b.wins), (0, 2));
+        assert_eq!(report.elo_difference, None);
+    }
+
+    #[test]
+    fn unknown_agents_are_rejected() {
+        let config = TournamentConfig {
+            agent_a: "nobody".into(),
+            ..Default::default()
+        };
+        assert!(run_tournament(&registry(), &config).is_err());
+    }
+}
@@ -74,3 +74,17 @@ pub fn button_interaction_event(
         }
     }
 }
+
+/// Replaces the label of a button spawned with [`create_button`].
+pub fn set_button_label(
+    button: Entity,
+    label: &str,
+    children: &Query<&Children>,
+    texts: &mut Query<&mut Text>,
+) {
+    for child in children.iter_descendants(button) {
+        if let Ok(mut text) = texts.get_mut(child) {
+            text.0 = label.into();
+        }
+    }
+}
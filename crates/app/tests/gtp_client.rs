@@ -0,0 +1,177 @@
+//! Drives `GtpAgent` against the scripted `gtp_stub` engine.
+
+use igo_core::{Game, Stone};
+use my_rougue::{AgentRegistry, Decision, GoAgent, GtpAgent, rules};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+/// Scratch directory holding the stub's command log and reply script.
+struct Stub {
+    dir: PathBuf,
+}
+
+impl Stub {
+    fn new(name: &str, replies: &[&str]) -> Self {
+        let dir = std::env::temp_dir().join(format!("igo_gtp_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let script = replies
+            .iter()
+            .map(|reply| format!("{reply}\n"))
+            .collect::<String>();
+        fs::write(dir.join("replies"), script).unwrap();
+        Self { dir }
+    }
+
+    fn args(&self) -> Vec<String> {
+        ["log", "replies"]
+            .map(|file| self.dir.join(file).display().to_string())
+            .into()
+    }
+
+    fn agent(&self) -> GtpAgent {
+        let mut command = vec![env!("CARGO_BIN_EXE_gtp_stub").to_string()];
+        command.extend(self.args());
+        GtpAgent::new(command)
+    }
+
+    /// Commands the engine has received so far, across restarts.
+    fn log(&self) -> Vec<String> {
+        fs::read_to_string(self.dir.join("log"))
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| *line != "quit")
+            .map(String::from)
+            .collect()
+    }
+}
+
+impl Drop for Stub {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn play(game: &mut Game, stone: Stone, x: u8, y: u8) {
+    let hand = rules::find_hand(game, stone, x, y).expect("point is playable");
+    assert!(game.put_hand(hand));
+}
+
+fn select(agent: &mut dyn GoAgent, game: &Game, turn: Stone) -> Decision {
+    agent.select_hand(game, turn, &AtomicBool::new(false))
+}
+
+/// Intersection of the decided hand; `None` for a pass.
+fn position(game: &Game, decision: Decision) -> Option<(u8, u8)> {
+    match decision {
+        Decision::Play(hand) => rules::hand_position(game, hand),
+        Decision::Resign => panic!("unexpected resignation"),
+    }
+}
+
+#[test]
+fn first_search_sets_up_the_whole_position() {
+    let stub = Stub::new("resync", &["E5"]);
+    let mut agent = stub.agent();
+    agent.set_komi(7.5);
+    let mut game = Game::new(9);
+    play(&mut game, Stone::Black, 2, 2);
+    play(&mut game, Stone::White, 6, 6);
+    let decision = select(&mut agent, &game, Stone::Black);
+    assert_eq!(position(&game, decision), Some((4, 4)));
+    assert_eq!(
+        stub.log(),
+        [
+            "boardsize 9",
+            "clear_board",
+            "komi 7.5",
+            "play B C7",
+            "play W G3",
+            "genmove B",
+        ]
+    );
+}
+
+#[test]
+fn later_searches_send_only_the_opponents_hand() {
+    let stub = Stub::new("incremental", &["E5", "d4", "pass"]);
+    let mut agent = stub.agent();
+    let mut game = Game::new(9);
+    let decision = select(&mut agent, &game, Stone::Black);
+    let Decision::Play(hand) = decision else {
+        panic!("unexpected resignation");
+    };
+    game.put_hand(hand);
+    play(&mut game, Stone::White, 2, 6);
+    // Vertices are case-insensitive.
+    let decision = select(&mut agent, &game, Stone::Black);
+    assert_eq!(position(&game, decision), Some((3, 5)));
+    let Decision::Play(hand) = decision else {
+        unreachable!()
+    };
+    game.put_hand(hand);
+    game.put_hand(rules::find_hand(&game, Stone::White, 6, 2).unwrap());
+    let decision = select(&mut agent, &game, Stone::Black);
+    assert_eq!(position(&game, decision), None);
+    assert_eq!(
+        stub.log()[3..],
+        [
+            "genmove B",
+            "play W C3",
+            "genmove B",
+            "play W G7",
+            "genmove B"
+        ]
+    );
+}
+
+#[test]
+fn engine_resignation_is_reported() {
+    let stub = Stub::new("resign", &["resign"]);
+    let mut agent = stub.agent();
+    let decision = select(&mut agent, &Game::new(9), Stone::White);
+    assert!(matches!(decision, Decision::Resign));
+}
+
+#[test]
+fn failures_pass_and_restart_the_engine() {
+    let stub = Stub::new("recovery", &["!fail", "!exit", "Z1", "D4"]);
+    let mut agent = stub.agent();
+    let game = Game::new(9);
+    // An error reply, a dead process and an off-board vertex each pass...
+    for _ in 0..3 {
+        let decision = select(&mut agent, &game, Stone::Black);
+        assert_eq!(position(&game, decision), None);
+    }
+    // ...and the next search starts a fresh engine from the full position.
+    let decision = select(&mut agent, &game, Stone::Black);
+    assert_eq!(position(&game, decision), Some((3, 5)));
+    let setups = stub
+        .log()
+        .iter()
+        .filter(|line| *line == "boardsize 9")
+        .count();
+    assert_eq!(setups, 4);
+}
+
+#[test]
+fn engine_path_may_contain_spaces() {
+    let stub = Stub::new("spaces", &["E5"]);
+    let engine_dir = stub.dir.join("engine dir");
+    fs::create_dir_all(&engine_dir).unwrap();
+    let engine = engine_dir.join("gtp stub");
+    fs::copy(Path::new(env!("CARGO_BIN_EXE_gtp_stub")), &engine).unwrap();
+    let config = configure::Config {
+        gtp_engine: Some(engine.display().to_string()),
+        gtp_engine_args: stub.args(),
+        ..Default::default()
+    };
+    let registry = AgentRegistry::from_config(&config);
+    let mut agent = registry.create(GtpAgent::NAME, 0).unwrap();
+    let game = Game::new(9);
+    let decision = select(&mut *agent, &game, Stone::Black);
+    assert_eq!(position(&game, decision), Some((4, 4)));
+}
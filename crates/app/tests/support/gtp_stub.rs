@@ -0,0 +1,52 @@
+//! Scripted GTP engine for the GTP client tests.
+//!
+//! Usage: `gtp_stub LOG REPLIES`. Every command received is appended to
+//! `LOG`. Each `genmove` takes the first line of `REPLIES` as its answer and
+//! removes it, so the script carries over when the client restarts the
+//! engine. The reply `!fail` answers with a GTP error and `!exit` makes the
+//! engine exit without answering; every other command succeeds silently.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufRead, Write},
+};
+
+fn next_reply(path: &str) -> String {
+    let script = fs::read_to_string(path).unwrap_or_default();
+    let mut lines = script.lines();
+    let reply = lines.next().unwrap_or("pass").to_string();
+    let rest = lines.map(|line| format!("{line}\n")).collect::<String>();
+    fs::write(path, rest).expect("replies file is writable");
+    reply
+}
+
+fn main() -> io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let (Some(log_path), Some(replies_path)) = (args.next(), args.next()) else {
+        eprintln!("usage: gtp_stub LOG REPLIES");
+        std::process::exit(2);
+    };
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)?;
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        writeln!(log, "{line}")?;
+        let response = match line.split_whitespace().next() {
+            Some("genmove") => match next_reply(&replies_path).as_str() {
+                "!fail" => "? cannot generate a move".to_string(),
+                "!exit" => return Ok(()),
+                reply => format!("= {reply}"),
+            },
+            _ => "=".to_string(),
+        };
+        write!(stdout, "{response}\n\n")?;
+        stdout.flush()?;
+        if line == "quit" {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -1 +1,85 @@
+use std::path::Path;
 
+pub const CONFIG_PATH: &str = "config.ron";
+
+/// Strength of the AI opponent chosen in the pre-game setup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+/// User settings persisted between runs.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Seed for every game's RNG; a fresh one is drawn per game when unset.
+    pub seed: Option<u64>,
+    /// Difficulty last chosen in the pre-game setup.
+    pub difficulty: Difficulty,
+    /// Program of an external engine to play through GTP, e.g. `gnugo`.
+    pub gtp_engine: Option<String>,
+    /// Arguments passed to `gtp_engine`, e.g. `["--mode", "gtp"]`.
+    pub gtp_engine_args: Vec<String>,
+}
+
+impl Config {
+    /// Reads the config at `path`, falling back to defaults when it is missing or invalid.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(text) => ron::from_str(&text).unwrap_or_else(|err| {
+                log::warn!("Invalid config {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(err) => {
+                log::debug!("No config at {}: {err}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let text = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(text) => text,
+            Err(err) => {
+                log::warn!("Cannot serialize config: {err}");
+                return;
+            }
+        };
+        if let Err(err) = std::fs::write(path, text) {
+            log::warn!("Cannot write config {}: {err}", path.display());
+        }
+    }
+
+    /// Overrides settings from command line arguments such as `--seed 42` or
+    /// `--gtp-engine gnugo --gtp-engine-arg --mode --gtp-engine-arg gtp`.
+    /// Engine arguments given on the command line replace the configured ones.
+    pub fn apply_args(&mut self, args: impl IntoIterator<Item = String>) {
+        let mut args = args.into_iter();
+        let mut engine_args = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => match args.next().map(|value| value.parse()) {
+                    Some(Ok(seed)) => self.seed = Some(seed),
+                    _ => log::warn!("--seed expects an unsigned integer"),
+                },
+                "--gtp-engine" => match args.next() {
+                    Some(engine) => self.gtp_engine = Some(engine),
+                    None => log::warn!("--gtp-engine expects the engine's program"),
+                },
+                "--gtp-engine-arg" => match args.next() {
+                    Some(arg) => engine_args.push(arg),
+                    None => log::warn!("--gtp-engine-arg expects an argument"),
+                },
+                _ => log::warn!("Unknown argument: {arg}"),
+            }
+        }
+        if !engine_args.is_empty() {
+            self.gtp_engine_args = engine_args;
+        }
+    }
+}